@@ -0,0 +1,3 @@
+create type mastodon_server_stream as enum ('polling', 'public', 'local');
+
+alter table mastodon_servers add column stream mastodon_server_stream not null default 'public';
@@ -0,0 +1 @@
+alter table mastodon_servers add column last_status_id text;
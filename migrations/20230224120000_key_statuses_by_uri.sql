@@ -0,0 +1,39 @@
+-- Statuses are now identified by their canonical ActivityPub URI, as the id
+-- depends on which server delivered them. The old id is kept, together with
+-- the server it is local to, so we can still resolve references to it.
+alter table scheduled_posts add column mastodon_uri text;
+alter table scheduled_posts add column server_id uuid;
+alter table mastodon_posts add column mastodon_uri text;
+
+-- We did not store the URI before, so rebuild it using the format Mastodon
+-- uses for its own statuses. This is exact for statuses that were local to
+-- the server we were listening to.
+update scheduled_posts sp
+set mastodon_uri = mi.url || 'users/' || sp.profile_name || '/statuses/' || sp.mastodon_id
+from mastodon_instances mi
+where mi.id = sp.instance_id;
+
+update mastodon_posts mp
+set mastodon_uri = sp.mastodon_uri
+from scheduled_posts sp
+where sp.mastodon_id = mp.mastodon_id;
+
+update mastodon_posts mp
+set mastodon_uri = mi.url || 'users/' || p.name || '/statuses/' || mp.mastodon_id
+from mastodon_instances mi, profiles p
+where mp.mastodon_uri is null and mi.id = mp.instance_id and p.user_id = mp.user_id;
+
+-- Anything left has no instance or profile to rebuild the URI from
+update scheduled_posts set mastodon_uri = 'urn:nostodon:legacy:' || mastodon_id where mastodon_uri is null;
+update mastodon_posts set mastodon_uri = 'urn:nostodon:legacy:' || mastodon_id where mastodon_uri is null;
+
+alter table scheduled_posts alter column mastodon_uri set not null;
+alter table mastodon_posts alter column mastodon_uri set not null;
+
+drop index scheduled_posts_mastodon_id_unique_idx;
+drop index mastodon_posts_mastodon_id_unique_idx;
+
+create unique index scheduled_posts_mastodon_uri_unique_idx on scheduled_posts (mastodon_uri);
+create index scheduled_posts_server_id_mastodon_id_idx on scheduled_posts (server_id, mastodon_id);
+create unique index mastodon_posts_mastodon_uri_unique_idx on mastodon_posts (mastodon_uri);
+create index mastodon_posts_mastodon_id_idx on mastodon_posts (mastodon_id);
@@ -0,0 +1,47 @@
+-- Users are now identified by their full `username@domain` handle, instead of
+-- `username.host`, which was ambiguous for usernames containing dots. Users
+-- that end up with the same handle are merged into the oldest one that has a
+-- published profile. The others are kept around, as their keys still signed
+-- events that were already published.
+alter table users add column merged_into uuid;
+
+drop index users_mastodon_user_unique_idx;
+
+with instances as (
+    select id, substring(url from '://([^/:]+)') as host from mastodon_instances
+), handles as (
+    select
+        u.id,
+        lower(coalesce(
+            p.name,
+            left(u.mastodon_user, length(u.mastodon_user) - length(i.host) - 1)
+        ) || '@' || i.host) as acct,
+        p.id is not null as has_profile,
+        u.created_at
+    from users u
+    join instances i on i.id = u.instance_id
+    left join profiles p on p.user_id = u.id
+), ranked as (
+    select
+        id,
+        acct,
+        first_value(id) over (
+            partition by acct order by has_profile desc, created_at, id
+        ) as survivor
+    from handles
+)
+update users u
+set
+    mastodon_user = r.acct,
+    merged_into = case when r.survivor <> u.id then r.survivor end
+from ranked r
+where r.id = u.id;
+
+insert into user_blacklists (user_id)
+select distinct u.merged_into
+from users u
+join user_blacklists b on b.user_id = u.id
+where u.merged_into is not null
+    and not exists (select 1 from user_blacklists where user_id = u.merged_into);
+
+create unique index users_mastodon_user_unique_idx on users (mastodon_user) where merged_into is null;
@@ -0,0 +1,3 @@
+create type scheduled_post_kind as enum ('post', 'deletion');
+
+alter table scheduled_posts add column kind scheduled_post_kind not null default 'post';
@@ -0,0 +1,21 @@
+alter type scheduled_post_kind add value 'edit';
+
+alter table scheduled_posts add column edited_at timestamptz;
+
+-- Every version of a status we mirrored, and the event it was mirrored as.
+-- The original version has no edited_at.
+create table mastodon_post_versions (
+    id uuid primary key default uuid_generate_v4(),
+    mastodon_uri text not null,
+    edited_at timestamptz,
+    nostr_id text not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create index if not exists mastodon_post_versions_mastodon_uri_idx on mastodon_post_versions (mastodon_uri);
+create unique index if not exists mastodon_post_versions_nostr_id_unique_idx on mastodon_post_versions (nostr_id);
+create trigger fill_mastodon_post_versions_updated_at_on_update before update on mastodon_post_versions for each row execute procedure fill_updated_at_on_update();
+
+insert into mastodon_post_versions (mastodon_uri, nostr_id, created_at)
+select mastodon_uri, nostr_id, created_at from mastodon_posts;
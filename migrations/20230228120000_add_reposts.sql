@@ -0,0 +1,3 @@
+alter type scheduled_post_kind add value 'repost';
+
+alter table scheduled_posts add column reblog_of text;
@@ -0,0 +1,5 @@
+alter table scheduled_posts add column depends_on text;
+
+update scheduled_posts set depends_on = coalesce(in_reply_to, reblog_of);
+
+create index scheduled_posts_depends_on_idx on scheduled_posts (depends_on);
@@ -0,0 +1,2 @@
+alter table scheduled_posts add column spoiler_text text not null default '';
+alter table scheduled_posts add column sensitive boolean not null default false;
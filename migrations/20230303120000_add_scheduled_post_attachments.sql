@@ -0,0 +1 @@
+alter table scheduled_posts add column attachments jsonb not null default '[]';
@@ -0,0 +1,2 @@
+alter table scheduled_posts add column hashtags text[] not null default '{}';
+alter table scheduled_posts add column mentions jsonb not null default '[]';
@@ -0,0 +1,2 @@
+alter table scheduled_posts add column emojis jsonb not null default '[]';
+alter table scheduled_posts add column profile_emojis jsonb not null default '[]';
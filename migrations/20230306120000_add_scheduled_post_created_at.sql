@@ -0,0 +1 @@
+alter table scheduled_posts add column status_created_at timestamptz;
@@ -0,0 +1 @@
+alter table scheduled_posts add column mastodon_url text;
@@ -0,0 +1,14 @@
+create type language_rule as enum ('allow', 'deny');
+
+create table language_rules (
+  id uuid primary key default uuid_generate_v4(),
+  language text not null,
+  rule language_rule not null,
+  created_at timestamptz not null default now(),
+  updated_at timestamptz not null default now()
+);
+
+create unique index if not exists language_rules_language_unique_idx on language_rules (language);
+create trigger fill_language_rules_updated_at_on_update before update on language_rules for each row execute procedure fill_updated_at_on_update();
+
+alter table scheduled_posts add column language text;
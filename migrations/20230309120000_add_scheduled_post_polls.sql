@@ -0,0 +1,3 @@
+alter table scheduled_posts add column poll_options jsonb not null default '[]';
+alter table scheduled_posts add column poll_multiple boolean not null default false;
+alter table scheduled_posts add column poll_expires_at timestamptz;
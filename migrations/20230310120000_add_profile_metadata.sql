@@ -0,0 +1,7 @@
+alter table scheduled_posts add column profile_website text not null default '';
+alter table scheduled_posts add column profile_fields jsonb not null default '[]';
+alter table scheduled_posts add column profile_bot boolean not null default false;
+
+alter table profiles add column website text not null default '';
+alter table profiles add column fields jsonb not null default '[]';
+alter table profiles add column bot boolean not null default false;
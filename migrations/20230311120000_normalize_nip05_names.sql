@@ -0,0 +1,5 @@
+-- NIP-05 local parts only allow lowercase letters, digits and `-_.`
+update profiles set nip05 = regexp_replace(lower(nip05), '[^a-z0-9._-]', '_', 'g');
+update scheduled_posts set profile_nip05 = regexp_replace(lower(profile_nip05), '[^a-z0-9._-]', '_', 'g');
+
+create index if not exists profiles_nip05_idx on profiles (nip05);
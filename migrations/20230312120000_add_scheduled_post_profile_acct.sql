@@ -0,0 +1,5 @@
+alter table scheduled_posts add column profile_acct text not null default '';
+
+update scheduled_posts sp set profile_acct = u.mastodon_user
+from users u
+where u.id = sp.user_id;
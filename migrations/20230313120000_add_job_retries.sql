@@ -0,0 +1,9 @@
+-- Failed jobs are retried with a backoff, and only left behind as dead letters
+-- once they run out of attempts. Jobs that errored before were never retried.
+alter type scheduled_post_status rename value 'errored' to 'dead';
+alter table scheduled_posts rename column fail_reason to last_error;
+
+alter table scheduled_posts add column attempts integer not null default 0;
+alter table scheduled_posts add column run_at timestamptz not null default now();
+
+create index if not exists scheduled_posts_new_run_at_idx on scheduled_posts (run_at) where status = 'new';
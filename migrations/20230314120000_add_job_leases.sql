@@ -0,0 +1,9 @@
+-- Running jobs are leased to the process working on them, so that the jobs of
+-- a process that died can be handed out again once their lease expires.
+alter table scheduled_posts add column leased_by uuid;
+alter table scheduled_posts add column lease_expires_at timestamptz;
+
+-- Nothing keeps the jobs left running so far alive
+update scheduled_posts set lease_expires_at = now() where status = 'running';
+
+create index if not exists scheduled_posts_running_lease_idx on scheduled_posts (lease_expires_at) where status = 'running';
@@ -0,0 +1,11 @@
+-- Failed jobs and jobs whose lease expired are both retried after an
+-- exponential backoff with jitter, so the delay is computed in one place.
+create or replace function scheduled_post_retry_delay(
+    attempts integer,
+    base_secs double precision,
+    max_secs double precision
+) returns interval as $$
+    select make_interval(
+        secs => least(base_secs * power(2, attempts), max_secs) * (0.5 + random() / 2)
+    )
+$$ language sql volatile;
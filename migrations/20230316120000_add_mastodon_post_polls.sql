@@ -0,0 +1,4 @@
+-- The NIP-88 poll of a mirrored status is published as a step of its own, so
+-- a retried job can tell whether it still has to publish it. Polls published
+-- before were recorded as versions of their status, and still go away with it.
+alter table mastodon_posts add column poll_nostr_id text;
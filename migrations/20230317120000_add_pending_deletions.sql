@@ -0,0 +1,3 @@
+-- A status deleted while its job is running is only turned into a deletion
+-- once the running job is done, so that the job cannot publish it afterwards.
+alter table scheduled_posts add column deletion_pending boolean not null default false;
@@ -0,0 +1,3 @@
+-- A status edited while its job is running is queued again as an edit once
+-- the running job is done, instead of being handed to a second worker.
+alter table scheduled_posts add column edit_pending boolean not null default false;
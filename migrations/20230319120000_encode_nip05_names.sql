@@ -0,0 +1,21 @@
+-- NIP-05 names used to replace the `@` of a handle with a `.`, and anything
+-- else they could not hold with a `_`, so different handles could end up with
+-- the same name. They are now encoded the same way as util::nip05_name does.
+create function pg_temp.nip05_name(acct text) returns text as $$
+    select coalesce(string_agg(
+        case
+            when c ~ '[a-z0-9.-]' then c
+            when c = '_' then '__'
+            when c = '@' then '_at_'
+            else '_x' || to_hex(ascii(c)) || '_'
+        end,
+        '' order by i
+    ), '')
+    from unnest(string_to_array(lower(acct), null)) with ordinality as t(c, i)
+$$ language sql immutable;
+
+update profiles p set nip05 = pg_temp.nip05_name(u.mastodon_user)
+from users u where u.id = p.user_id;
+
+update scheduled_posts set profile_nip05 = pg_temp.nip05_name(profile_acct)
+where profile_acct <> '';
@@ -0,0 +1,9 @@
+-- Every NIP-05 name belongs to a single profile. Users merged into another
+-- one had the same handle, so their profiles give up the name to it. Names
+-- are built from unique handles otherwise, so any duplicate left fails here.
+update profiles p set nip05 = ''
+from users u
+where u.id = p.user_id and u.merged_into is not null;
+
+drop index if exists profiles_nip05_idx;
+create unique index profiles_nip05_unique_idx on profiles (nip05) where nip05 <> '';
@@ -0,0 +1,4 @@
+-- Replies to statuses we have not seen keep the id of their parent on the
+-- server that delivered them, so that the poster can look the parent up
+-- instead of the listener.
+alter table scheduled_posts add column in_reply_to_id text;
@@ -0,0 +1,3 @@
+-- Custom emoji are part of a published profile, so changing only them has to
+-- be noticed as well.
+alter table profiles add column emojis jsonb not null default '[]';
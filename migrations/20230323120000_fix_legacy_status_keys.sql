@@ -0,0 +1,53 @@
+-- Statuses stored before they were keyed by URI had theirs rebuilt using the
+-- format Mastodon uses for its own statuses, which is only right for statuses
+-- that were local to the server we were listening to. The others go back to
+-- their legacy key, so that a made-up URI never stands for a real one.
+create temporary table listened_hosts as
+select distinct on (host) id as server_id, host
+from (select id, substring(instance_url from '://([^/:]+)') as host from mastodon_servers) s
+order by host, server_id;
+
+create temporary table federated_legacy_posts as
+select mp.mastodon_uri, mp.mastodon_id
+from mastodon_posts mp
+join mastodon_instances mi on mi.id = mp.instance_id
+where mp.mastodon_uri like mi.url || 'users/%/statuses/' || mp.mastodon_id
+    and substring(mi.url from '://([^/:]+)') not in (select host from listened_hosts)
+    and not exists (
+        select 1 from scheduled_posts sp
+        where sp.mastodon_uri = mp.mastodon_uri and sp.server_id is not null
+    );
+
+update mastodon_post_versions v
+set mastodon_uri = 'urn:nostodon:legacy:' || f.mastodon_id
+from federated_legacy_posts f
+where v.mastodon_uri = f.mastodon_uri;
+
+update mastodon_posts mp
+set mastodon_uri = 'urn:nostodon:legacy:' || f.mastodon_id
+from federated_legacy_posts f
+where mp.mastodon_uri = f.mastodon_uri;
+
+update scheduled_posts sp
+set mastodon_uri = 'urn:nostodon:legacy:' || sp.mastodon_id
+from mastodon_instances mi
+where sp.server_id is null
+    and mi.id = sp.instance_id
+    and substring(mi.url from '://([^/:]+)') not in (select host from listened_hosts);
+
+-- They also never got the server that delivered them, so references to their
+-- ids did not resolve. Local statuses came from the server of their instance,
+-- and everything came from the only server if there is just one.
+update scheduled_posts sp
+set server_id = h.server_id
+from mastodon_instances mi, listened_hosts h
+where sp.server_id is null
+    and mi.id = sp.instance_id
+    and h.host = substring(mi.url from '://([^/:]+)');
+
+update scheduled_posts
+set server_id = (select id from mastodon_servers)
+where server_id is null and (select count(*) from mastodon_servers) = 1;
+
+drop table federated_legacy_posts;
+drop table listened_hosts;
@@ -0,0 +1,4 @@
+-- A lease is a token for a single attempt at a job, which says nothing about
+-- who holds it. Jobs also record the worker process that last leased them, so
+-- that a stuck or dead job can be traced back to it.
+alter table scheduled_posts add column worker_id text;
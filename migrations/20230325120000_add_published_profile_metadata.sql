@@ -0,0 +1,4 @@
+-- The content of the metadata event last published for each profile. It also
+-- depends on how we are configured to present mirrored profiles, so comparing
+-- the Mastodon profile alone misses changes to the templates or NIP-05 domain.
+alter table profiles add column metadata text;
@@ -0,0 +1,4 @@
+-- Jobs that errored before failed jobs were retried were left behind as dead
+-- letters without a single retry. They are the only dead jobs that made no
+-- attempts, and get all of them now.
+update scheduled_posts set status = 'new', run_at = now() where status = 'dead' and attempts = 0;
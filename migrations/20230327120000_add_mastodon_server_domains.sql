@@ -0,0 +1 @@
+alter table mastodon_servers add column domain text;
@@ -1,10 +1,16 @@
 use std::{
+    collections::BTreeMap,
     future::Future,
+    sync::Mutex,
     time::{Duration, Instant},
 };
 
 use eyre::Result;
-use metrics::{describe_counter, increment_counter, register_counter, register_histogram};
+use metrics::{
+    describe_counter, describe_gauge, gauge, increment_counter, register_counter,
+    register_histogram,
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use tokio::time::timeout_at;
 use tracing::trace;
 
@@ -15,12 +21,46 @@ pub const TASK_TIMEOUT_COUNT: &str = "nostodon_task_timeout_count";
 pub const TASK_TIME_ELAPSED: &str = "nostodon_task_time_elapsed_ms";
 pub const TASK_TIME_ELAPSED_HISTOGRAM: &str = "nostodon_task_elapsed_histogram";
 pub const POSTS_CREATED: &str = "nostodon_posts_created_count";
+pub const POSTS_DELETED: &str = "nostodon_posts_deleted_count";
+pub const POSTS_EDITED: &str = "nostodon_posts_edited_count";
+pub const POSTS_REPOSTED: &str = "nostodon_posts_reposted_count";
+pub const THREADS_COMPLETED: &str = "nostodon_threads_completed_count";
+pub const JOBS_RETRIED: &str = "nostodon_jobs_retried_count";
+pub const JOBS_DEAD: &str = "nostodon_jobs_dead_count";
+pub const JOBS_RECLAIMED: &str = "nostodon_jobs_reclaimed_count";
+pub const DELETIONS_SCHEDULED: &str = "nostodon_deletions_scheduled_count";
 pub const PROFILES_UPDATED: &str = "nostodon_profiles_updated_count";
+pub const MASTODON_STREAM_RECONNECTS: &str = "nostodon_mastodon_stream_reconnect_count";
+pub const MASTODON_TIMELINE_GAPS: &str = "nostodon_mastodon_timeline_gap_count";
+pub const COMPONENT_READY: &str = "nostodon_component_ready";
+pub const WORKER_INFO: &str = "nostodon_worker_info";
+
+/// Whether each long running component is currently able to do its work,
+/// keyed by component name.
+static READINESS: Mutex<BTreeMap<String, bool>> = Mutex::new(BTreeMap::new());
+
+/// Records whether a component is ready. Components only count towards
+/// readiness once they reported in.
+pub fn set_ready<S: Into<String>>(component: S, ready: bool) {
+    let component = component.into();
+
+    gauge!(COMPONENT_READY, if ready { 1.0 } else { 0.0 }, "component" => component.clone());
+    READINESS.lock().unwrap().insert(component, ready);
+}
+
+/// Returns the readiness of every component that reported in.
+pub fn readiness() -> BTreeMap<String, bool> {
+    READINESS.lock().unwrap().clone()
+}
 
 pub struct Provider;
 
 impl Provider {
-    pub fn setup() {
+    /// Installs the Prometheus recorder and describes our metrics, returning
+    /// the handle that renders them.
+    pub fn setup() -> Result<PrometheusHandle> {
+        let handle = PrometheusBuilder::new().install_recorder()?;
+
         describe_counter!(
             EVENTS_SKIPPED,
             "Counter of events that have been skipped because of some rule"
@@ -50,7 +90,60 @@ impl Provider {
 
         describe_counter!(POSTS_CREATED, "Number of posts that have been created");
 
+        describe_counter!(POSTS_DELETED, "Number of posts that have been deleted");
+
+        describe_counter!(POSTS_EDITED, "Number of posts that have been edited");
+
+        describe_counter!(POSTS_REPOSTED, "Number of boosts that have been reposted");
+
+        describe_counter!(
+            THREADS_COMPLETED,
+            "Number of replies whose missing ancestors had to be fetched and mirrored"
+        );
+
+        describe_counter!(
+            JOBS_RETRIED,
+            "Number of failed jobs that have been scheduled to be retried"
+        );
+
+        describe_counter!(
+            JOBS_DEAD,
+            "Number of jobs given up on after failing every attempt"
+        );
+
+        describe_counter!(
+            JOBS_RECLAIMED,
+            "Number of running jobs handed out again after their lease expired"
+        );
+
+        describe_counter!(
+            DELETIONS_SCHEDULED,
+            "Counter of deletions that have been scheduled to be posted"
+        );
+
         describe_counter!(PROFILES_UPDATED, "Number of posts that have been created");
+
+        describe_counter!(
+            MASTODON_STREAM_RECONNECTS,
+            "Number of times a streaming connection to a Mastodon server had to be reestablished"
+        );
+
+        describe_counter!(
+            MASTODON_TIMELINE_GAPS,
+            "Number of gaps detected while reading a Mastodon timeline"
+        );
+
+        describe_gauge!(
+            COMPONENT_READY,
+            "Whether a component, like a Mastodon stream or the job queue listener, is ready"
+        );
+
+        describe_gauge!(
+            WORKER_INFO,
+            "Always 1, labelled with the worker id this process leases jobs under"
+        );
+
+        Ok(handle)
     }
 }
 
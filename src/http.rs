@@ -0,0 +1,182 @@
+//! Built-in HTTP servers, answering the NIP-05 lookups for the identifiers we
+//! advertise on mirrored profiles along with health checks, and exposing
+//! metrics on an address of their own.
+
+use std::{collections::HashMap, net::SocketAddr};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use clap::Parser;
+use eyre::{Result, WrapErr};
+use futures_util::future::try_join;
+use metrics_exporter_prometheus::PrometheusHandle;
+use nostr_sdk::prelude::{FromBech32, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use tokio::task::{self, JoinHandle};
+use tracing::{info, warn};
+
+use crate::{health::readiness, postgres::Postgres};
+
+/// How long clients may cache a NIP-05 document, in seconds
+const NIP05_MAX_AGE: u32 = 300;
+
+#[derive(Debug, Clone, Parser)]
+pub struct HttpConfig {
+    #[clap(
+        long = "http-address",
+        env = "NOSTODON_HTTP_ADDRESS",
+        default_value = "0.0.0.0:8080"
+    )]
+    /// Address to serve NIP-05 documents and health checks on
+    pub address: SocketAddr,
+
+    #[clap(
+        long = "metrics-address",
+        env = "NOSTODON_METRICS_ADDRESS",
+        default_value = "127.0.0.1:9100"
+    )]
+    /// Address to serve Prometheus metrics on, kept apart from the public one
+    pub metrics_address: SocketAddr,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip05Query {
+    name: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Nip05Document {
+    names: HashMap<String, String>,
+    relays: HashMap<String, Vec<String>>,
+}
+
+async fn nostr_json(State(postgres): State<Postgres>, Query(query): Query<Nip05Query>) -> Response {
+    // Web clients fetch these documents from other origins
+    let cors = (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+
+    match nip05_document(&postgres, query.name).await {
+        Ok(document) => (
+            [
+                cors,
+                (
+                    header::CACHE_CONTROL,
+                    &*format!("public, max-age={NIP05_MAX_AGE}"),
+                ),
+            ],
+            Json(document),
+        )
+            .into_response(),
+        Err(e) => {
+            warn!(error = %e, "Could not build a NIP-05 document");
+            (StatusCode::INTERNAL_SERVER_ERROR, [cors]).into_response()
+        }
+    }
+}
+
+/// Looks up a single name, as we do not list every mirrored user at once.
+/// Names hold the full handle, so they resolve the same under every domain
+/// the server is reachable at.
+async fn nip05_document(postgres: &Postgres, name: Option<String>) -> Result<Nip05Document> {
+    let mut document = Nip05Document::default();
+
+    let name = match name {
+        // Names are already encoded, but clients may not keep their case
+        Some(name) => name.to_lowercase(),
+        None => return Ok(document),
+    };
+
+    if let Some(npub) = postgres.fetch_nip05_public_key(name.clone()).await? {
+        let public_key = XOnlyPublicKey::from_bech32(npub)?.to_string();
+        let relays = postgres.fetch_nostr_relays().await?;
+
+        if !relays.is_empty() {
+            document.relays.insert(public_key.clone(), relays);
+        }
+
+        document.names.insert(name, public_key);
+    }
+
+    Ok(document)
+}
+
+async fn metrics(State(metrics): State<PrometheusHandle>) -> String {
+    metrics.render()
+}
+
+/// The process is up, which is all liveness is about.
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Ready when the database answers and every Mastodon stream and the job queue
+/// listener are connected. Lists the state of each of them.
+async fn readyz(State(postgres): State<Postgres>) -> (StatusCode, String) {
+    let mut ready = true;
+    let mut body = String::new();
+
+    let database = match postgres.health_check().await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(error = %e, "Database health check failed");
+            false
+        }
+    };
+
+    let components = std::iter::once(("postgres".to_string(), database)).chain(readiness());
+
+    for (component, component_ready) in components {
+        ready &= component_ready;
+
+        body.push_str(&format!(
+            "{component}: {}\n",
+            if component_ready {
+                "ready"
+            } else {
+                "not ready"
+            }
+        ));
+    }
+
+    match ready {
+        true => (StatusCode::OK, body),
+        false => (StatusCode::SERVICE_UNAVAILABLE, body),
+    }
+}
+
+/// Binds the servers before spawning them, so that a busy address is reported
+/// right away. The task only returns if one of the servers stops.
+pub fn spawn(
+    postgres: Postgres,
+    metrics: PrometheusHandle,
+    config: HttpConfig,
+) -> Result<JoinHandle<Result<()>>> {
+    let app = Router::new()
+        .route("/.well-known/nostr.json", get(nostr_json))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(postgres);
+
+    let metrics_app = Router::new()
+        .route("/metrics", get(self::metrics))
+        .with_state(metrics);
+
+    let server = axum::Server::try_bind(&config.address)
+        .wrap_err_with(|| format!("could not listen on {}", config.address))?
+        .serve(app.into_make_service());
+
+    let metrics_server = axum::Server::try_bind(&config.metrics_address)
+        .wrap_err_with(|| format!("could not listen on {}", config.metrics_address))?
+        .serve(metrics_app.into_make_service());
+
+    info!(address = %config.address, metrics_address = %config.metrics_address, "Serving HTTP");
+
+    Ok(task::spawn(async move {
+        try_join(server, metrics_server).await?;
+        Ok(())
+    }))
+}
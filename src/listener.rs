@@ -1,8 +1,12 @@
+use std::{cmp::Ordering, collections::HashMap};
+
 use eyre::{eyre, Result};
 use futures_util::future::try_join_all;
-use mastodon_async::{prelude::Status, Visibility};
+use mastodon_async::Visibility;
 use metrics::increment_counter;
-use tracing::{debug, error};
+use sqlx::types::Json;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::{debug, error, warn};
 
 use crate::{
     health::*,
@@ -29,54 +33,219 @@ pub async fn spawn(postgres: Postgres) -> Result<()> {
     Ok(())
 }
 
-async fn spawn_listener(server: MastodonServer, postgres: Postgres) -> Result<()> {
-    let mastodon = Mastodon::connect(&server)?;
+/// How many times a status is fetched again after failing to be processed
+/// before the timeline cursor moves past it anyway.
+const MAX_STATUS_ATTEMPTS: u32 = 3;
+
+/// Tracks which statuses of a timeline were processed, so that its cursor
+/// never moves past one that failed. The next catch-up resumes from the
+/// cursor and fetches the failed statuses again.
+#[derive(Debug, Default)]
+struct TimelineProgress {
+    newest: Option<String>,
+    failures: HashMap<String, u32>,
+}
+
+impl TimelineProgress {
+    fn processed(&mut self, status_id: String) {
+        self.failures.remove(&status_id);
+
+        let is_newer = match self.newest.as_deref() {
+            Some(newest) => cmp_status_ids(&status_id, newest) == Ordering::Greater,
+            None => true,
+        };
+
+        if is_newer {
+            self.newest = Some(status_id);
+        }
+    }
+
+    /// Records a failed attempt at a status. Returns true if it failed too
+    /// many times, and was given up on.
+    fn failed(&mut self, status_id: String) -> bool {
+        let attempts = self.failures.entry(status_id.clone()).or_default();
+        *attempts += 1;
+
+        if *attempts < MAX_STATUS_ATTEMPTS {
+            return false;
+        }
+
+        self.processed(status_id);
+        true
+    }
+
+    /// Where the cursor can move to, if no failed status is waiting for a
+    /// retry.
+    fn cursor(&self) -> Option<&str> {
+        if self.failures.is_empty() {
+            self.newest.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+async fn spawn_listener(mut server: MastodonServer, postgres: Postgres) -> Result<()> {
+    let mastodon = Mastodon::connect(&server, postgres.clone())?;
+
+    // The domain is stored for the poster, which resolves handles too
+    match mastodon.fetch_domain().await {
+        Ok(domain) => {
+            postgres.update_server_domain(server.id, &domain).await?;
+            server.domain = Some(domain);
+        }
+        Err(e) => {
+            warn!(instance = %server.instance_url, error = %e, "Could not fetch the domain of the server, handles of its accounts might be wrong");
+        }
+    }
 
     let mut rx = mastodon.update_stream().await?;
+    let mut progress = TimelineProgress::default();
 
-    loop {
-        match rx.try_recv() {
-            Ok(status) => match process_status(postgres.clone(), status)
-                .time_as("mastodon.process_status")
-                .await
-            {
-                Ok(_) => continue,
-                Err(e) => {
-                    error!(instance = %server.instance_url, error = %e, "Error while processing update");
-                    continue;
+    while let Some(event) = rx.recv().await {
+        match event {
+            MastodonEvent::Update(status) => {
+                let status_id = status.id.to_string();
+
+                match process_status(postgres.clone(), &server, status, None)
+                    .time_as("mastodon.process_status")
+                    .await
+                {
+                    Ok(_) => progress.processed(status_id),
+                    Err(e) => {
+                        error!(instance = %server.instance_url, id = status_id, error = %e, "Error while processing update");
+
+                        if progress.failed(status_id) {
+                            increment_counter!(MASTODON_TIMELINE_GAPS, "server" => server.instance_url.clone(), "reason" => "processing_failed");
+                        }
+                    }
+                }
+
+                // Statuses that failed are fetched again from the cursor
+                if let Some(cursor) = progress.cursor() {
+                    if let Err(e) = postgres
+                        .advance_server_cursor(server.id, cursor.to_string())
+                        .await
+                    {
+                        error!(instance = %server.instance_url, error = %e, "Error while advancing the timeline cursor");
+                    }
                 }
-            },
-            Err(_) => continue,
+            }
+            MastodonEvent::Edit(status, edited_at) => {
+                if let Err(e) = process_status(postgres.clone(), &server, status, Some(edited_at))
+                    .time_as("mastodon.process_status")
+                    .await
+                {
+                    error!(instance = %server.instance_url, error = %e, "Error while processing edit");
+                }
+            }
+            MastodonEvent::Delete(status_id) => {
+                if let Err(e) = process_deletion(postgres.clone(), &server, status_id)
+                    .time_as("mastodon.process_deletion")
+                    .await
+                {
+                    error!(instance = %server.instance_url, error = %e, "Error while processing deletion");
+                }
+            }
+        }
+    }
+
+    Err(eyre!(
+        "The timeline of {} stopped sending updates",
+        server.instance_url
+    ))
+}
+
+/// Schedules the removal of a mirrored status from Nostr. The id is local to
+/// the server that delivered the deletion, so statuses we never got from it
+/// are ignored.
+async fn process_deletion(
+    postgres: Postgres,
+    server: &MastodonServer,
+    status_id: String,
+) -> Result<()> {
+    let uri = match postgres
+        .fetch_status_uri(server.id, status_id.clone())
+        .await?
+    {
+        Some(uri) => uri,
+        None => {
+            debug!(id = &status_id, instance = %server.instance_url, reason = "unknown_status", "Skipping deletion");
+            return Ok(());
         }
+    };
+
+    if postgres.listener().schedule_deletion(uri).await? {
+        increment_counter!(DELETIONS_SCHEDULED);
     }
 
-    #[allow(unreachable_code)]
     Ok(())
 }
 
-async fn process_status(postgres: Postgres, status: Status) -> Result<()> {
-    let visibility_text = match status.visibility {
+/// Resolves the canonical URI of the status being replied to, if the server
+/// that delivered the reply already gave it to us. Otherwise the poster looks
+/// it up, so that the listener does not wait on the server for every reply.
+async fn resolve_parent_uri(
+    postgres: &Postgres,
+    server: &MastodonServer,
+    status: &Status,
+) -> Result<Option<String>> {
+    match &status.in_reply_to_id {
+        Some(parent_id) => {
+            postgres
+                .fetch_status_uri(server.id, parent_id.clone())
+                .await
+        }
+        None => Ok(None),
+    }
+}
+
+fn visibility_label(status: &Status) -> &'static str {
+    match status.visibility {
         Visibility::Direct => "direct",
         Visibility::Private => "private",
         Visibility::Unlisted => "unlisted",
         Visibility::Public => "public",
-    };
+    }
+}
 
-    let instance_url = extract_instance_url(status.url.as_ref().unwrap())?;
+/// Reduces a language tag like `en-US` to its ISO 639 code, which is three
+/// letters long for languages that have no ISO 639-1 code.
+fn language_code(tag: &str) -> Option<String> {
+    let language = tag.split(&['-', '_'][..]).next()?;
+
+    match language.len() {
+        2 | 3 if language.chars().all(|c| c.is_ascii_alphabetic()) => Some(language.to_lowercase()),
+        _ => None,
+    }
+}
+
+struct Author {
+    instance: MastodonInstance,
+    user: User,
+    acct: String,
+}
+
+/// Finds the mirrored user for the author of a status, checking it against
+/// the visibility and blacklist rules. Returns `None` if the status should not
+/// be mirrored.
+async fn resolve_author(
+    postgres: &Postgres,
+    server: &MastodonServer,
+    status: &Status,
+) -> Result<Option<Author>> {
+    let visibility_text = visibility_label(status);
+
+    // Boosts do not always have an url of their own
+    let instance_url = extract_instance_url(status.url.as_ref().unwrap_or(&status.account.url))?;
 
     if status.visibility != Visibility::Public {
         debug!(id = &status.id.to_string(), instance = %&instance_url, reason = "post_visibility", "Skipping status");
         increment_counter!(EVENTS_SKIPPED, "visibility" => visibility_text, "reason" => "post_visibility");
 
-        return Ok(());
-    }
-
-    if status.url.is_none() {
-        todo!("No Url");
+        return Ok(None);
     }
 
-    let instance_url = extract_instance_url(status.url.as_ref().unwrap())?;
-
     let instance = postgres
         .fetch_or_create_instance(instance_url.as_str())
         .await?;
@@ -84,43 +253,312 @@ async fn process_status(postgres: Postgres, status: Status) -> Result<()> {
     if instance.blacklisted {
         debug!(id = &status.id.to_string(), instance = %&instance_url, reason = "instance_blacklist", "Skipping status");
         increment_counter!(EVENTS_SKIPPED, "visibility" => visibility_text, "reason" => "instance_blacklist");
+
+        return Ok(None);
     }
 
-    let nip05 = format!(
-        "{}.{}",
-        status.account.username.clone(),
-        instance_url.host().unwrap()
-    );
+    let acct = full_acct(&status.account.acct, &server.local_domain()?);
 
     let user = postgres
-        .fetch_or_create_user(instance.id, nip05.clone())
+        .fetch_or_create_user(instance.id, acct.clone())
         .await?;
 
     if postgres.is_user_blacklisted(user.id).await? {
         debug!(id = &status.id.to_string(), instance = %&instance_url, reason = "user_blacklist", "Skipping status");
         increment_counter!(EVENTS_SKIPPED, "visibility" => visibility_text, "reason" => "user_blacklist");
 
+        return Ok(None);
+    }
+
+    Ok(Some(Author {
+        instance,
+        user,
+        acct,
+    }))
+}
+
+async fn process_status(
+    postgres: Postgres,
+    server: &MastodonServer,
+    status: Status,
+    edited_at: Option<OffsetDateTime>,
+) -> Result<()> {
+    match status.reblog.clone() {
+        Some(original) => process_reblog(postgres, server, status, *original).await,
+        None => {
+            schedule_status(postgres, server, status, edited_at).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Mirrors a boost as a repost by the booster. The boosted status is
+/// scheduled first, so the repost has something to point to, unless the
+/// booster is skipped anyway.
+async fn process_reblog(
+    postgres: Postgres,
+    server: &MastodonServer,
+    status: Status,
+    original: Status,
+) -> Result<()> {
+    let original_uri = original.uri.clone();
+
+    let author = match resolve_author(&postgres, server, &status).await? {
+        Some(author) => author,
+        None => return Ok(()),
+    };
+
+    if !schedule_status(postgres.clone(), server, original, None).await? {
+        debug!(
+            id = &status.id.to_string(),
+            reason = "reblog_skipped",
+            "Skipping status"
+        );
         return Ok(());
     }
 
-    let profile = Profile::build(instance.id, user.id, &status)?;
+    let profile = Profile::build(author.instance.id, author.user.id, &author.acct, &status);
 
     postgres
         .listener()
         .push(ScheduledPost {
-            content: status.content,
-            instance_id: instance.id,
-            user_id: user.id,
+            kind: ScheduledPostKind::Repost,
+            content: String::new(),
+            spoiler_text: String::new(),
+            sensitive: false,
+            language: None,
+            attachments: Json::default(),
+            hashtags: vec![],
+            mentions: Json::default(),
+            emojis: Json::default(),
+            poll_options: Json::default(),
+            poll_multiple: false,
+            poll_expires_at: None,
+            instance_id: author.instance.id,
+            user_id: author.user.id,
+            server_id: Some(server.id),
             mastodon_id: status.id.to_string(),
-            in_reply_to: status.in_reply_to_id,
+            mastodon_uri: status.uri.clone(),
+            mastodon_url: status.url.clone(),
+            status_created_at: Some(status.created_at),
+            edited_at: None,
+            in_reply_to: None,
+            in_reply_to_id: None,
+            reblog_of: Some(original_uri.clone()),
+            depends_on: Some(original_uri),
+            profile_acct: profile.acct,
             profile_name: profile.name,
             profile_display_name: profile.display_name,
             profile_about: profile.about,
             profile_picture: profile.picture,
             profile_nip05: profile.nip05,
             profile_banner: profile.banner,
+            profile_emojis: Json(profile.emojis),
+            profile_website: profile.website,
+            profile_fields: Json(profile.fields),
+            profile_bot: profile.bot,
         })
         .await?;
 
     Ok(())
 }
+
+/// Schedules a status to be mirrored as a note. Returns false if it was
+/// skipped because of some rule.
+async fn schedule_status(
+    postgres: Postgres,
+    server: &MastodonServer,
+    status: Status,
+    edited_at: Option<OffsetDateTime>,
+) -> Result<bool> {
+    let in_reply_to = resolve_parent_uri(&postgres, server, &status).await?;
+
+    match prepare_status(&postgres, server, status, edited_at, in_reply_to).await? {
+        Some(post) => {
+            postgres.listener().push(post).await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Collects the attachments of a status that can be linked to. Attachments
+/// still being processed by the server have no url yet.
+fn collect_attachments(status: &Status) -> Json<Vec<Attachment>> {
+    let attachments = status
+        .media_attachments
+        .iter()
+        .filter_map(|media| {
+            let url = media.url.clone().or_else(|| media.remote_url.clone())?;
+            let dimensions = media.meta.as_ref().and_then(|meta| meta.original.as_ref());
+
+            Some(Attachment {
+                mime_type: media.mime_type().map(str::to_string),
+                width: dimensions.and_then(|d| d.width),
+                height: dimensions.and_then(|d| d.height),
+                blurhash: media.blurhash.clone(),
+                description: media.description.clone(),
+                url,
+            })
+        })
+        .collect();
+
+    Json(attachments)
+}
+
+fn collect_hashtags(status: &Status) -> Vec<String> {
+    let mut hashtags: Vec<String> = status
+        .tags
+        .iter()
+        .map(|tag| tag.name.to_lowercase())
+        .collect();
+
+    hashtags.sort();
+    hashtags.dedup();
+
+    hashtags
+}
+
+fn collect_mentions(server: &MastodonServer, status: &Status) -> Result<Json<Vec<Mention>>> {
+    let domain = server.local_domain()?;
+    let mentions = status
+        .mentions
+        .iter()
+        .map(|mention| {
+            Ok(Mention {
+                acct: full_acct(&mention.acct, &domain),
+                url: mention.url.clone(),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Json(mentions))
+}
+
+/// Builds the job mirroring a status as a note, or `None` if it should be
+/// skipped because of some rule. The URI of the status it replies to has to be
+/// resolved by the caller.
+pub async fn prepare_status(
+    postgres: &Postgres,
+    server: &MastodonServer,
+    status: Status,
+    edited_at: Option<OffsetDateTime>,
+    in_reply_to: Option<String>,
+) -> Result<Option<ScheduledPost>> {
+    let language = status.language.as_deref().and_then(language_code);
+    let poll = status.poll.clone();
+
+    if !postgres.is_language_allowed(language.clone()).await? {
+        debug!(
+            id = &status.id.to_string(),
+            language,
+            reason = "language",
+            "Skipping status"
+        );
+        increment_counter!(EVENTS_SKIPPED, "visibility" => visibility_label(&status), "reason" => "language");
+
+        return Ok(None);
+    }
+
+    let author = match resolve_author(postgres, server, &status).await? {
+        Some(author) => author,
+        None => return Ok(None),
+    };
+
+    let profile = Profile::build(author.instance.id, author.user.id, &author.acct, &status);
+
+    Ok(Some(ScheduledPost {
+        kind: ScheduledPostKind::Post,
+        content: status.content.clone(),
+        spoiler_text: status.spoiler_text.clone(),
+        sensitive: status.sensitive,
+        language,
+        attachments: collect_attachments(&status),
+        hashtags: collect_hashtags(&status),
+        mentions: collect_mentions(server, &status)?,
+        emojis: Json(
+            status
+                .emojis
+                .iter()
+                .map(|emoji| Emoji {
+                    shortcode: emoji.shortcode.clone(),
+                    url: emoji.url.clone(),
+                })
+                .collect(),
+        ),
+        poll_options: Json(
+            poll.iter()
+                .flat_map(|poll| &poll.options)
+                .map(|option| option.title.clone())
+                .collect(),
+        ),
+        poll_multiple: matches!(&poll, Some(poll) if poll.multiple),
+        poll_expires_at: match poll.as_ref().and_then(|poll| poll.expires_at.as_deref()) {
+            Some(expires_at) => Some(OffsetDateTime::parse(expires_at, &Rfc3339)?),
+            None => None,
+        },
+        instance_id: author.instance.id,
+        user_id: author.user.id,
+        server_id: Some(server.id),
+        mastodon_id: status.id.to_string(),
+        mastodon_uri: status.uri.clone(),
+        mastodon_url: status.url.clone(),
+        status_created_at: Some(status.created_at),
+        edited_at,
+        depends_on: in_reply_to.clone(),
+        in_reply_to,
+        in_reply_to_id: status.in_reply_to_id.clone(),
+        reblog_of: None,
+        profile_acct: profile.acct,
+        profile_name: profile.name,
+        profile_display_name: profile.display_name,
+        profile_about: profile.about,
+        profile_picture: profile.picture,
+        profile_nip05: profile.nip05,
+        profile_banner: profile.banner,
+        profile_emojis: Json(profile.emojis),
+        profile_website: profile.website,
+        profile_fields: Json(profile.fields),
+        profile_bot: profile.bot,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{language_code, TimelineProgress};
+
+    #[test]
+    fn reduces_language_tags_to_their_code() {
+        assert_eq!(language_code("en-US").as_deref(), Some("en"));
+        assert_eq!(language_code("PT_br").as_deref(), Some("pt"));
+        assert_eq!(language_code("yue").as_deref(), Some("yue"));
+        assert_eq!(language_code("english"), None);
+        assert_eq!(language_code("1"), None);
+    }
+
+    #[test]
+    fn keeps_the_cursor_behind_failed_statuses() {
+        let mut progress = TimelineProgress::default();
+
+        progress.processed("100".to_string());
+        assert!(!progress.failed("101".to_string()));
+        progress.processed("102".to_string());
+
+        assert_eq!(progress.cursor(), None);
+
+        progress.processed("101".to_string());
+
+        assert_eq!(progress.cursor(), Some("102"));
+    }
+
+    #[test]
+    fn gives_up_on_statuses_that_keep_failing() {
+        let mut progress = TimelineProgress::default();
+
+        assert!(!progress.failed("100".to_string()));
+        assert!(!progress.failed("100".to_string()));
+        assert!(progress.failed("100".to_string()));
+
+        assert_eq!(progress.cursor(), Some("100"));
+    }
+}
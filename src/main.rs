@@ -4,11 +4,13 @@ use tokio::task;
 use tracing::info;
 
 mod health;
+mod http;
 mod listener;
 mod mastodon;
 mod nostr;
 mod poster;
 mod postgres;
+mod render;
 mod util;
 
 use crate::postgres::*;
@@ -18,6 +20,12 @@ pub struct Config {
     #[clap(flatten)]
     pub postgres: postgres::PostgresConfig,
 
+    #[clap(flatten)]
+    pub poster: poster::PosterConfig,
+
+    #[clap(flatten)]
+    pub http: http::HttpConfig,
+
     #[clap(long = "skip-posting", short = 'p', env = "NOSTODON_SKIP_POSTING")]
     /// Only schedule posting on the database, do not actually post them
     pub skip_posting: bool,
@@ -30,7 +38,7 @@ async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     info!("Tracing layer initialized.");
 
-    health::Provider::setup();
+    let metrics = health::Provider::setup()?;
     info!("Metrics initialized");
 
     let postgres = Postgres::init(config.clone().postgres).await?;
@@ -38,11 +46,16 @@ async fn main() -> Result<()> {
     postgres.health_check().await?;
     postgres.migrate().await?;
 
+    let http = http::spawn(postgres.clone(), metrics, config.http.clone())?;
+
     if !config.skip_posting {
-        task::spawn(poster::spawn(postgres.clone()));
+        task::spawn(poster::spawn(postgres.clone(), config.poster.clone()));
     }
 
-    listener::spawn(postgres).await?;
+    tokio::select! {
+        result = listener::spawn(postgres) => result?,
+        result = http => result??,
+    }
 
     Ok(())
 }
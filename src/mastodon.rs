@@ -1,80 +1,554 @@
 use std::time::Duration;
 
-use eyre::{ErrReport, Result};
-use mastodon_async::prelude::{Status, StatusId};
+use eyre::{eyre, ErrReport, Result};
+use futures_util::StreamExt;
+use mastodon_async::{
+    entities::status::{Mention, Tag},
+    prelude::StatusId,
+    Visibility,
+};
+use metrics::increment_counter;
+use serde::Deserialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::{
-    sync::broadcast::{self, Receiver, Sender},
-    task, time,
+    sync::mpsc::{self, Receiver, Sender},
+    task,
+    time::sleep,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    health::{
+        set_ready, Timeable, Timeoutable, MASTODON_STREAM_RECONNECTS, MASTODON_TIMELINE_GAPS,
+    },
+    postgres::{MastodonServer, MastodonStream, Postgres},
+    util::{cmp_status_ids, SseEvent, SseParser},
 };
-use tracing::error;
 
-use crate::{health::Timeable, postgres::MastodonServer};
+const POLLING_INTERVAL: Duration = Duration::from_secs(2);
+const STREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Servers send a heartbeat every 15 seconds, so a quieter connection is dead
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Maximum page size allowed by the Mastodon timeline API
+const TIMELINE_PAGE_SIZE: usize = 40;
+/// Events read ahead of the listener, before the timeline waits for it
+const EVENT_BUFFER_SIZE: usize = 128;
+
+/// Kind of a media attachment, as described by the Mastodon API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Image,
+    Gifv,
+    Video,
+    Audio,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A media attachment, as described by the Mastodon API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaAttachment {
+    #[serde(rename = "type")]
+    pub kind: MediaKind,
+    pub url: Option<String>,
+    pub remote_url: Option<String>,
+    pub description: Option<String>,
+    pub blurhash: Option<String>,
+    pub meta: Option<MediaMeta>,
+}
+
+impl MediaAttachment {
+    /// Servers transcode videos and animated GIFs to MP4 and audio to MP3.
+    /// Images keep their format, so it is guessed from the file extension.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self.kind {
+            MediaKind::Gifv | MediaKind::Video => Some("video/mp4"),
+            MediaKind::Audio => Some("audio/mpeg"),
+            MediaKind::Image => {
+                let url = self.url.as_ref().or(self.remote_url.as_ref())?;
+                mime_guess::from_path(reqwest::Url::parse(url).ok()?.path()).first_raw()
+            }
+            MediaKind::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaMeta {
+    pub original: Option<MediaDimensions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaDimensions {
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomEmoji {
+    pub shortcode: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PollOption {
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Poll {
+    pub expires_at: Option<String>,
+    pub multiple: bool,
+    pub options: Vec<PollOption>,
+}
+
+/// A profile field, along with its verification.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountField {
+    pub name: String,
+    pub value: String,
+    /// Set when the server found a link back to the profile at the url in
+    /// `value`
+    pub verified_at: Option<String>,
+}
+
+/// The author of a status, with the fields we mirror.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub username: String,
+    /// Equals `username` for local users, includes `@domain` for remote ones
+    pub acct: String,
+    pub display_name: String,
+    /// Bio, as html
+    pub note: String,
+    /// Url of the profile page
+    pub url: String,
+    pub avatar: String,
+    pub header: String,
+    pub bot: Option<bool>,
+    /// Custom emoji used in the display name and bio
+    #[serde(default)]
+    pub emojis: Vec<CustomEmoji>,
+    #[serde(default)]
+    pub fields: Vec<AccountField>,
+}
+
+/// A status, with the fields we mirror. mastodon-async leaves out some of
+/// them, such as the type of media attachments, polls and profile fields.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Status {
+    pub id: StatusId,
+    /// Canonical ActivityPub URI
+    pub uri: String,
+    /// Link to the status on its own server
+    pub url: Option<String>,
+    pub account: Account,
+    pub in_reply_to_id: Option<String>,
+    pub reblog: Option<Box<Status>>,
+    /// Body of the status, as html
+    pub content: String,
+    #[serde(with = "time::serde::iso8601")]
+    pub created_at: OffsetDateTime,
+    #[serde(default)]
+    pub emojis: Vec<CustomEmoji>,
+    pub sensitive: bool,
+    pub spoiler_text: String,
+    pub visibility: Visibility,
+    #[serde(default)]
+    pub media_attachments: Vec<MediaAttachment>,
+    pub mentions: Vec<Mention>,
+    pub tags: Vec<Tag>,
+    pub language: Option<String>,
+    pub poll: Option<Poll>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MastodonEvent {
+    /// A new status
+    Update(Status),
+    /// A new version of a status, and when it was edited
+    Edit(Status, OffsetDateTime),
+    /// A deleted status, by its id on the server that sent the event
+    Delete(String),
+}
 
 #[async_trait::async_trait]
 pub trait MastodonClient {
     type StatusId;
 
-    async fn update_stream(&self) -> Result<Receiver<Status>>;
+    async fn update_stream(&self) -> Result<Receiver<MastodonEvent>>;
+
+    /// Fetches the statuses a status replies to, from the oldest to the parent.
+    async fn fetch_ancestors(&self, id: &Self::StatusId) -> Result<Vec<Status>>;
 }
 
 pub struct Mastodon {
     server: MastodonServer,
-    sender: Sender<Status>,
-    _receiver: Receiver<Status>,
+    postgres: Postgres,
+    http: reqwest::Client,
 }
 
 impl Mastodon {
-    pub fn connect(server: &MastodonServer) -> Result<Self> {
+    pub fn connect(server: &MastodonServer, postgres: Postgres) -> Result<Self> {
         let server = server.clone();
-        let (sender, _receiver) = broadcast::channel(128);
 
         Ok(Self {
             server,
-            sender,
-            _receiver,
+            postgres,
+            http: reqwest::Client::new(),
         })
     }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let data = self.server.as_data();
+        let url = reqwest::Url::parse(&data.base)?.join(path)?;
+
+        Ok(self
+            .http
+            .get(url)
+            .bearer_auth(&data.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Fetches the domain in the handles of the server's own accounts, which
+    /// is not the domain of its url when the server is set up to use another.
+    pub async fn fetch_domain(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Instance {
+            uri: String,
+        }
+
+        let instance: Instance = self
+            .get("api/v1/instance")
+            .time_as("mastodon.get_instance")
+            .await?;
+
+        // Some servers report an url rather than a domain
+        match reqwest::Url::parse(&instance.uri) {
+            Ok(url) => Ok(url
+                .host_str()
+                .ok_or_else(|| eyre!("instance uri has no host"))?
+                .to_string()),
+            Err(_) => Ok(instance.uri),
+        }
+    }
+}
+
+/// Reads the public timeline of a server. Every poll or reconnect resumes from
+/// the server's cursor, which the listener only moves past statuses it has
+/// processed, so that the ones that failed are fetched again.
+struct Timeline {
+    server: MastodonServer,
+    postgres: Postgres,
+    http: reqwest::Client,
+    sender: Sender<MastodonEvent>,
+}
+
+impl Timeline {
+    /// Name the timeline reports its readiness under
+    fn component(&self) -> String {
+        format!("mastodon_stream:{}", self.server.instance_url)
+    }
+
+    fn local(&self) -> bool {
+        self.server.stream == MastodonStream::Local
+    }
+
+    /// Hands an event to the listener, waiting for it to make room if it is
+    /// behind, so that nothing is dropped.
+    async fn send(&self, event: MastodonEvent) -> Result<()> {
+        self.sender
+            .send(event)
+            .await
+            .map_err(|_| eyre!("the listener is gone"))
+    }
+
+    async fn fetch_page(&self, min_id: Option<&str>) -> Result<Vec<Status>> {
+        let mut url =
+            reqwest::Url::parse(&self.server.instance_url)?.join("api/v1/timelines/public")?;
+
+        url.query_pairs_mut()
+            .append_pair("local", &self.local().to_string())
+            .append_pair("limit", &TIMELINE_PAGE_SIZE.to_string());
+
+        if let Some(min_id) = min_id {
+            url.query_pairs_mut().append_pair("min_id", min_id);
+        }
+
+        Ok(self
+            .http
+            .get(url)
+            .bearer_auth(&self.server.token)
+            .send()
+            .time_as("mastodon.get_public_timeline")
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Pages forward from the cursor until there are no newer statuses left,
+    /// sending them to the subscribers from oldest to newest. Without a
+    /// cursor, only the newest page is fetched.
+    async fn catch_up(&self) -> Result<()> {
+        let mut cursor = self.postgres.fetch_server_cursor(self.server.id).await?;
+        let mut pages = 0;
+
+        loop {
+            let mut page = self.fetch_page(cursor.as_deref()).await?;
+            let is_last_page = cursor.is_none() || page.len() < TIMELINE_PAGE_SIZE;
+
+            if page.is_empty() {
+                break;
+            }
+
+            pages += 1;
+
+            // The API returns statuses from newest to oldest
+            page.sort_by(|a, b| cmp_status_ids(a.id.as_ref(), b.id.as_ref()));
+
+            for status in page {
+                cursor = Some(status.id.to_string());
+                self.send(MastodonEvent::Update(status)).await?;
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        // A single page would not have been enough to see everything that was
+        // posted since the last time we looked.
+        if pages > 1 {
+            increment_counter!(MASTODON_TIMELINE_GAPS, "server" => self.server.instance_url.clone(), "reason" => "page_overflow");
+        }
+
+        Ok(())
+    }
+
+    /// Parses a single server-sent event from the streaming API, if it is one
+    /// we are interested in.
+    fn parse_event(event: &str, data: &str) -> Result<Option<MastodonEvent>> {
+        let event = match event {
+            "update" => MastodonEvent::Update(serde_json::from_str(data)?),
+            "status.update" => {
+                let value: serde_json::Value = serde_json::from_str(data)?;
+                let edited_at = match value["edited_at"].as_str() {
+                    Some(edited_at) => OffsetDateTime::parse(edited_at, &Rfc3339)?,
+                    None => OffsetDateTime::now_utc(),
+                };
+
+                MastodonEvent::Edit(serde_json::from_value(value)?, edited_at)
+            }
+            "delete" => MastodonEvent::Delete(data.to_string()),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+
+    async fn connect(&self) -> Result<reqwest::Response> {
+        let mut url = reqwest::Url::parse(&self.server.instance_url)?.join("api/v1/streaming")?;
+
+        // The token goes in the query, as the server might redirect us to a
+        // different host for streaming, which drops the authorization header.
+        url.query_pairs_mut()
+            .append_pair(
+                "stream",
+                if self.local() {
+                    "public:local"
+                } else {
+                    "public"
+                },
+            )
+            .append_pair("access_token", &self.server.token);
+
+        Ok(self
+            .http
+            .get(url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .time_as("mastodon.connect_stream")
+            .with_timeout(STREAM_CONNECT_TIMEOUT)
+            .await??
+            .error_for_status()?)
+    }
+
+    /// Forwards status updates, edits and deletions from a streaming
+    /// connection, returning when the server closes the connection.
+    async fn consume(&self, response: reqwest::Response) -> Result<()> {
+        let mut chunks = response.bytes_stream();
+        let mut parser = SseParser::default();
+
+        while let Some(chunk) = chunks.next().with_timeout(STREAM_IDLE_TIMEOUT).await? {
+            for SseEvent { event, data } in parser.feed(&chunk?) {
+                match Self::parse_event(&event, &data) {
+                    Ok(Some(event)) => self.send(event).await?,
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!(error = %e, server = self.server.instance_url, event, "Could not handle streaming event");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn stream(&self) -> Result<()> {
+        // Connect first, so that the catch-up overlaps with the stream instead
+        // of leaving a window where statuses could be missed.
+        let response = self.connect().await?;
+        set_ready(self.component(), true);
+
+        self.catch_up().await?;
+        self.consume(response).await
+    }
 }
 
 #[async_trait::async_trait]
 impl MastodonClient for Mastodon {
     type StatusId = StatusId;
 
-    async fn update_stream(&self) -> Result<Receiver<Status>> {
-        let sender = self.sender.clone();
-        let server = self.server.clone();
+    async fn update_stream(&self) -> Result<Receiver<MastodonEvent>> {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER_SIZE);
+
+        let timeline = Timeline {
+            server: self.server.clone(),
+            postgres: self.postgres.clone(),
+            http: self.http.clone(),
+            sender,
+        };
 
         task::spawn(async move {
-            let task = || async {
-                let sender = sender.clone();
-                let client = mastodon_async::Mastodon::from(server.clone().as_data());
-                let events = client
-                    .get_public_timeline(false)
-                    .time_as("mastodon.get_public_timeline")
-                    .await?;
-
-                for event in events {
-                    sender
-                        .clone()
-                        .send(event)
-                        .expect("error: mastodon sender has no subscribers");
+            let server = timeline.server.clone();
+
+            set_ready(timeline.component(), false);
+
+            if server.stream == MastodonStream::Polling {
+                loop {
+                    match timeline.catch_up().await {
+                        Ok(_) => set_ready(timeline.component(), true),
+                        Err(e) => {
+                            set_ready(timeline.component(), false);
+                            error!(error = %e, server = server.instance_url, "Got an error while getting updates");
+                        }
+                    }
+
+                    sleep(POLLING_INTERVAL).await;
                 }
+            }
 
-                Ok::<_, ErrReport>(())
-            };
+            let mut backoff = RECONNECT_BACKOFF_MIN;
 
             loop {
-                match task().await {
-                    Ok(_) => {}
+                info!(server = server.instance_url, stream = ?server.stream, "Connecting to streaming API");
+
+                let result = timeline.stream().await;
+                set_ready(timeline.component(), false);
+
+                match result {
+                    Ok(_) => {
+                        warn!(
+                            server = server.instance_url,
+                            "Streaming connection closed by the server"
+                        );
+                        backoff = RECONNECT_BACKOFF_MIN;
+                    }
                     Err(e) => {
-                        error!(error = %e, server = server.instance_url, "Got an error while getting updates");
+                        error!(error = %e, server = server.instance_url, "Got an error on the streaming connection, polling until reconnect");
+
+                        // Keep the timeline flowing while the stream is unavailable
+                        if let Err(e) = timeline.catch_up().await {
+                            error!(error = %e, server = server.instance_url, "Got an error while getting updates");
+                        }
                     }
                 }
 
-                time::sleep(Duration::from_secs(2)).await;
+                increment_counter!(MASTODON_STREAM_RECONNECTS, "server" => server.instance_url.clone());
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
             }
+
+            #[allow(unreachable_code)]
+            Ok::<_, ErrReport>(())
         });
 
-        Ok(self.sender.subscribe())
+        Ok(receiver)
+    }
+
+    async fn fetch_ancestors(&self, id: &Self::StatusId) -> Result<Vec<Status>> {
+        #[derive(Deserialize)]
+        struct Context {
+            ancestors: Vec<Status>,
+        }
+
+        let context: Context = self
+            .get(&format!("api/v1/statuses/{id}/context"))
+            .time_as("mastodon.get_context")
+            .await?;
+
+        Ok(context.ancestors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MediaKind, Status};
+
+    #[test]
+    fn parses_statuses_with_their_media() {
+        let status: Status = serde_json::from_value(serde_json::json!({
+            "id": "110000000000000001",
+            "uri": "https://example.com/users/alice/statuses/1",
+            "url": "https://example.com/@alice/1",
+            "account": {
+                "username": "alice",
+                "acct": "alice",
+                "display_name": "Alice",
+                "note": "<p>Hi</p>",
+                "url": "https://example.com/@alice",
+                "avatar": "https://example.com/avatar.png",
+                "header": "https://example.com/header.png",
+                "bot": false,
+                "fields": [{ "name": "Web", "value": "x", "verified_at": null }]
+            },
+            "in_reply_to_id": null,
+            "reblog": null,
+            "content": "<p>Hello</p>",
+            "created_at": "2023-03-01T12:00:00.000Z",
+            "emojis": [],
+            "sensitive": false,
+            "spoiler_text": "",
+            "visibility": "public",
+            "media_attachments": [
+                { "type": "gifv", "url": "https://example.com/a.mp4" },
+                { "type": "image", "url": "https://example.com/b.png?size=large" },
+                { "type": "image", "url": "https://example.com/c" },
+                { "type": "hologram", "url": "https://example.com/d" }
+            ],
+            "mentions": [],
+            "tags": [],
+            "language": "en",
+            "poll": null
+        }))
+        .unwrap();
+
+        let media = &status.media_attachments;
+
+        assert_eq!(status.account.fields.len(), 1);
+        assert_eq!(media[0].mime_type(), Some("video/mp4"));
+        assert_eq!(media[1].mime_type(), Some("image/png"));
+        assert_eq!(media[2].mime_type(), None);
+        assert_eq!(media[3].kind, MediaKind::Unknown);
     }
 }
@@ -1,38 +1,424 @@
+use std::collections::HashMap;
+
+use ::time::UtcOffset;
+use clap::Parser;
 use eyre::Result;
 use nostr_sdk::prelude::*;
 
 use crate::{
     health::Timeable,
-    postgres::{job_queue::ScheduledPost, Postgres, Profile},
+    postgres::{
+        job_queue::{Attachment, Emoji, ScheduledPost},
+        Postgres, Profile,
+    },
+    render::render,
+    util::fill_template,
 };
 
+/// NIP-88 poll
+const POLL_KIND: Kind = Kind::Custom(1068);
+
 #[derive(Debug, Clone, Default)]
 pub struct Note {
     pub text: String,
     pub tags: Vec<Tag>,
+    /// When the note was written, if not now
+    pub created_at: Option<Timestamp>,
 }
 
 impl Note {
     pub async fn build(postgres: &Postgres, post: &ScheduledPost) -> Result<Self> {
         let mut tags = vec![];
 
-        if let Some(root) = &post.in_reply_to {
-            if let Some(event_id) = postgres.fetch_nostr_id(root.to_string()).await? {
-                // Tracks the relationship between replies
-                tags.push(Tag::Event(
-                    EventId::from_bech32(event_id)?,
-                    // TODO: Add one of the configured relays here
-                    None,
-                    Some("root".into()),
+        if let Some(parent_uri) = &post.in_reply_to {
+            let thread = postgres.fetch_thread(parent_uri.to_string()).await?;
+
+            // Every relay gets our events, so any of them is a good hint. The
+            // oldest one is picked so that the hint does not change between events
+            let relay = postgres.fetch_nostr_relays().await?.into_iter().next();
+
+            // NIP-10: direct replies to the root only carry the root marker.
+            // When the root was never mirrored, only the parent is marked.
+            match (thread.first(), thread.last()) {
+                (Some(parent), Some(root)) if root.is_root && thread.len() > 1 => {
+                    tags.push(Tag::Event(
+                        EventId::from_bech32(&root.nostr_id)?,
+                        relay.clone(),
+                        Some(Marker::Root),
+                    ));
+                    tags.push(Tag::Event(
+                        EventId::from_bech32(&parent.nostr_id)?,
+                        relay.clone(),
+                        Some(Marker::Reply),
+                    ));
+                }
+                (Some(parent), _) => {
+                    tags.push(Tag::Event(
+                        EventId::from_bech32(&parent.nostr_id)?,
+                        relay.clone(),
+                        Some(if parent.is_root {
+                            Marker::Root
+                        } else {
+                            Marker::Reply
+                        }),
+                    ));
+                }
+                _ => {}
+            }
+
+            if let Some(parent) = thread.first() {
+                tags.push(Tag::PubKey(
+                    XOnlyPublicKey::from_bech32(&parent.nostr_public_key)?,
+                    relay,
                 ));
             }
         }
 
+        let mut mentions = HashMap::new();
+
+        for mention in &post.mentions.0 {
+            let npub = match postgres.fetch_public_key(mention.acct.clone()).await? {
+                Some(npub) => npub,
+                // Left as a link to the Mastodon profile
+                None => continue,
+            };
+            let public_key = XOnlyPublicKey::from_bech32(&npub)?;
+
+            if !tags
+                .iter()
+                .any(|tag| matches!(tag, Tag::PubKey(pk, _) if *pk == public_key))
+            {
+                tags.push(Tag::PubKey(public_key, None));
+            }
+
+            // NIP-27
+            mentions.insert(mention.url.clone(), format!("nostr:{npub}"));
+        }
+
+        for hashtag in &post.hashtags {
+            tags.push(Tag::Generic(
+                TagKind::Custom("t".to_string()),
+                vec![hashtag.clone()],
+            ));
+        }
+
+        let mut text = render(&post.content, &mentions)?;
+
+        if !post.poll_options.0.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&render_poll(post));
+        }
+
+        for attachment in &post.attachments.0 {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&attachment.url);
+
+            tags.push(imeta_tag(attachment));
+        }
+
+        // NIP-36, with the warning repeated for clients that ignore the tag
+        if !post.spoiler_text.is_empty() {
+            tags.push(Tag::ContentWarning {
+                reason: Some(post.spoiler_text.clone()),
+            });
+            text = format!("CW: {}\n\n{}", post.spoiler_text, text);
+        } else if post.sensitive {
+            tags.push(Tag::ContentWarning { reason: None });
+        }
+
+        tags.extend(emoji_tags(&post.emojis.0, &[&text]));
+
+        // NIP-32, under the standard of the code: three letter codes are for
+        // languages without a two letter one
+        if let Some(language) = &post.language {
+            let namespace = match language.len() {
+                2 => "ISO-639-1",
+                _ => "ISO-639-3",
+            };
+
+            tags.push(Tag::Generic(
+                TagKind::Custom("L".to_string()),
+                vec![namespace.to_string()],
+            ));
+            tags.push(Tag::Generic(
+                TagKind::Custom("l".to_string()),
+                vec![language.clone(), namespace.to_string()],
+            ));
+        }
+
         Ok(Self {
-            text: html2md::parse_html(&post.content),
+            text,
+            tags,
+            created_at: None,
+        })
+    }
+}
+
+/// Lists the options of a poll, as votes can only be cast on Mastodon.
+fn render_poll(post: &ScheduledPost) -> String {
+    let marker = if post.poll_multiple { "☐" } else { "○" };
+
+    let mut lines: Vec<String> = post
+        .poll_options
+        .0
+        .iter()
+        .map(|option| format!("{marker} {option}"))
+        .collect();
+
+    if let Some(expires_at) = post.poll_expires_at {
+        let expires_at = expires_at.to_offset(UtcOffset::UTC);
+
+        lines.push(format!(
+            "Poll ends {} {:02}:{:02} UTC",
+            expires_at.date(),
+            expires_at.hour(),
+            expires_at.minute()
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// A NIP-88 poll mirroring the poll of a status. Responses to it do not count
+/// as votes, so it links back to the note and the original status.
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub question: String,
+    pub tags: Vec<Tag>,
+    pub created_at: Option<Timestamp>,
+}
+
+impl Poll {
+    pub fn build(post: &ScheduledPost, note_id: EventId) -> Result<Option<Self>> {
+        if post.poll_options.0.is_empty() {
+            return Ok(None);
+        }
+
+        let mut question = render(&post.content, &HashMap::new())?;
+
+        if let Some(url) = &post.mastodon_url {
+            question = format!("{question}\n\nVotes are only counted on Mastodon: {url}");
+        }
+
+        let mut tags: Vec<Tag> = post
+            .poll_options
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                Tag::Generic(
+                    TagKind::Custom("option".to_string()),
+                    vec![i.to_string(), option.clone()],
+                )
+            })
+            .collect();
+
+        tags.push(Tag::Generic(
+            TagKind::Custom("polltype".to_string()),
+            vec![match post.poll_multiple {
+                true => "multiplechoice".to_string(),
+                false => "singlechoice".to_string(),
+            }],
+        ));
+
+        if let Some(expires_at) = post.poll_expires_at {
+            tags.push(Tag::Generic(
+                TagKind::Custom("endsAt".to_string()),
+                vec![expires_at.unix_timestamp().to_string()],
+            ));
+        }
+
+        tags.push(Tag::Event(note_id, None, Some("mention".into())));
+
+        Ok(Some(Self {
+            question,
             tags,
+            created_at: None,
+        }))
+    }
+}
+
+/// How mirrored profiles present themselves. Templates can use the
+/// `{display_name}`, `{name}`, `{acct}`, `{instance}` and `{url}` placeholders.
+#[derive(Debug, Clone, Parser)]
+pub struct ProfileConfig {
+    #[clap(
+        long = "nip05-domain",
+        env = "NOSTODON_NIP05_DOMAIN",
+        default_value = "nostodon.org"
+    )]
+    /// Domain of the NIP-05 identifiers of mirrored users, a template so that
+    /// each mirrored instance can get its own
+    pub nip05_domain: String,
+
+    #[clap(
+        long = "display-name-template",
+        env = "NOSTODON_DISPLAY_NAME_TEMPLATE",
+        default_value = "[Unofficial Mirror] {display_name}"
+    )]
+    /// Template for the display name of mirrored users
+    pub display_name_template: String,
+
+    #[clap(
+        long = "about-template",
+        env = "NOSTODON_ABOUT_TEMPLATE",
+        default_value = "THIS IS AN UNNOFICIAL MIRROR. CHECK THE PROFILE FOR CORRECT INFO.\n\n{about}"
+    )]
+    /// Template for the bio of mirrored users, with `{about}` as their own bio
+    pub about_template: String,
+}
+
+impl ProfileConfig {
+    /// Renders the content of the metadata event of a profile.
+    pub fn metadata(&self, profile: &Profile) -> Result<String> {
+        let (display_name, about, nip05) = self.fill(profile)?;
+
+        profile_metadata(profile, display_name, about, nip05)
+    }
+
+    /// Returns the display name, bio and NIP-05 identifier of a profile.
+    fn fill(&self, profile: &Profile) -> Result<(String, String, String)> {
+        let about = profile_about(profile)?;
+        let instance = profile
+            .acct
+            .split_once('@')
+            .map_or("", |(_, instance)| instance);
+
+        let values = [
+            ("display_name", profile.display_name.as_str()),
+            ("name", profile.name.as_str()),
+            ("acct", profile.acct.as_str()),
+            ("instance", instance),
+            ("url", profile.website.as_str()),
+            ("about", about.as_str()),
+        ];
+
+        Ok((
+            fill_template(&self.display_name_template, &values),
+            fill_template(&self.about_template, &values),
+            format!(
+                "{}@{}",
+                profile.nip05,
+                fill_template(&self.nip05_domain, &values)
+            ),
+        ))
+    }
+}
+
+/// Renders the bio of a profile, followed by the fields its server verified.
+fn profile_about(profile: &Profile) -> Result<String> {
+    let mut sections = vec![render(&profile.about, &HashMap::new())?];
+
+    let fields = profile
+        .fields
+        .iter()
+        .map(|field| {
+            Ok(format!(
+                "✓ {}: {}",
+                field.name,
+                render(&field.value, &HashMap::new())?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    sections.push(fields.join("\n"));
+    sections.retain(|section| !section.is_empty());
+
+    Ok(sections.join("\n\n"))
+}
+
+/// Builds the content of a metadata event. Images are left out unless they
+/// are absolute urls to an actual upload, and bots get the NIP-24 `bot` flag.
+fn profile_metadata(
+    profile: &Profile,
+    display_name: String,
+    about: String,
+    nip05: String,
+) -> Result<String> {
+    let mut metadata = Metadata::new()
+        .name(&profile.name)
+        .display_name(display_name)
+        .nip05(nip05)
+        .about(about);
+
+    if let Some(url) = image_url(&profile.picture) {
+        metadata = metadata.picture(url);
+    }
+
+    if let Some(url) = image_url(&profile.banner) {
+        metadata = metadata.banner(url);
+    }
+
+    if let Ok(url) = Url::parse(&profile.website) {
+        metadata = metadata.website(url);
+    }
+
+    let mut json = serde_json::to_value(&metadata)?;
+
+    if profile.bot {
+        json["bot"] = serde_json::Value::Bool(true);
+    }
+
+    Ok(json.to_string())
+}
+
+/// Mastodon fills in placeholders for missing avatars and headers, which are
+/// relative on some servers.
+fn image_url(url: &str) -> Option<Url> {
+    Url::parse(url)
+        .ok()
+        .filter(|url| !url.path().ends_with("/missing.png"))
+}
+
+/// Builds the NIP-48 `proxy` tag pointing to the ActivityPub object an event
+/// mirrors.
+pub fn proxy_tag(mastodon_uri: &str) -> Tag {
+    Tag::Generic(
+        TagKind::Custom("proxy".to_string()),
+        vec![mastodon_uri.to_string(), "activitypub".to_string()],
+    )
+}
+
+/// Builds NIP-30 `emoji` tags for the custom emoji used in any of `texts`.
+fn emoji_tags(emojis: &[Emoji], texts: &[&str]) -> Vec<Tag> {
+    emojis
+        .iter()
+        .filter(|emoji| {
+            let shortcode = format!(":{}:", emoji.shortcode);
+            texts.iter().any(|text| text.contains(&shortcode))
+        })
+        .map(|emoji| {
+            Tag::Generic(
+                TagKind::Custom("emoji".to_string()),
+                vec![emoji.shortcode.clone(), emoji.url.clone()],
+            )
         })
+        .collect()
+}
+
+/// Builds the NIP-92 `imeta` tag describing an attachment linked in a note.
+fn imeta_tag(attachment: &Attachment) -> Tag {
+    let mut fields = vec![format!("url {}", attachment.url)];
+
+    if let Some(mime_type) = &attachment.mime_type {
+        fields.push(format!("m {mime_type}"));
+    }
+
+    if let (Some(width), Some(height)) = (attachment.width, attachment.height) {
+        fields.push(format!("dim {width}x{height}"));
+    }
+
+    if let Some(blurhash) = &attachment.blurhash {
+        fields.push(format!("blurhash {blurhash}"));
     }
+
+    if let Some(description) = &attachment.description {
+        fields.push(format!("alt {description}"));
+    }
+
+    Tag::Generic(TagKind::Custom("imeta".to_string()), fields)
 }
 
 #[derive(Debug, Clone)]
@@ -64,30 +450,296 @@ impl Nostr {
         Ok(this)
     }
 
+    /// Signs an event with the client's keys. The client always dates events
+    /// with the current time, so they are built by hand.
+    fn sign(
+        &self,
+        kind: Kind,
+        content: &str,
+        tags: &[Tag],
+        created_at: Option<Timestamp>,
+    ) -> Result<Event> {
+        let keys = self.client.keys();
+        let pubkey = keys.public_key();
+        let created_at = created_at.unwrap_or_else(Timestamp::now);
+
+        let id = EventId::new(&pubkey, created_at, &kind, tags, content);
+        let message = Message::from_slice(id.as_bytes())?;
+
+        Ok(Event {
+            id,
+            pubkey,
+            created_at,
+            kind,
+            tags: tags.to_vec(),
+            content: content.to_string(),
+            sig: Secp256k1::new().sign_schnorr(&message, &keys.key_pair()?),
+            ots: None,
+        })
+    }
+
     pub async fn publish(&self, note: Note) -> Result<EventId> {
+        let event = self.sign(Kind::TextNote, &note.text, &note.tags, note.created_at)?;
+
         Ok(self
             .client
-            .publish_text_note(&note.text, &note.tags)
+            .send_event(event)
             .time_as("nostr.publish.client_publish")
             .await?)
     }
 
-    pub async fn update_user_profile(&self, profile: Profile) -> Result<EventId> {
-        let metadata = Metadata::new()
-            .name(&profile.name)
-            .display_name(format!("[Unofficial Mirror] {}", profile.display_name))
-            .banner(Url::parse(&profile.banner)?)
-            .picture(Url::parse(&profile.picture)?)
-            .nip05(format!("{}@nostodon.org", &profile.nip05))
-            .about(format!(
-                "THIS IS AN UNNOFICIAL MIRROR. CHECK THE PROFILE FOR CORRECT INFO.\n\n{}",
-                profile.about
-            ));
+    pub async fn publish_poll(&self, poll: Poll) -> Result<EventId> {
+        let event = self.sign(POLL_KIND, &poll.question, &poll.tags, poll.created_at)?;
 
         Ok(self
             .client
-            .update_profile(metadata)
+            .send_event(event)
+            .time_as("nostr.publish_poll.client_send_event")
+            .await?)
+    }
+
+    /// Publishes a NIP-18 repost of an event by `author`, which can be found
+    /// on `relay`.
+    pub async fn repost(
+        &self,
+        nostr_id: &str,
+        author: XOnlyPublicKey,
+        relay: Option<String>,
+        created_at: Option<Timestamp>,
+        extra_tags: &[Tag],
+    ) -> Result<EventId> {
+        let mut tags = vec![
+            Tag::Event(EventId::from_bech32(nostr_id)?, relay.clone(), None),
+            Tag::PubKey(author, relay),
+        ];
+        tags.extend_from_slice(extra_tags);
+
+        let event = self.sign(Kind::Repost, "", &tags, created_at)?;
+
+        Ok(self
+            .client
+            .send_event(event)
+            .time_as("nostr.repost.client_repost_event")
+            .await?)
+    }
+
+    /// Publishes a NIP-09 deletion for events previously published with the
+    /// same keys.
+    pub async fn delete(
+        &self,
+        nostr_ids: &[String],
+        reason: &str,
+        extra_tags: &[Tag],
+    ) -> Result<EventId> {
+        let mut tags = nostr_ids
+            .iter()
+            .map(|id| Ok(Tag::Event(EventId::from_bech32(id)?, None, None)))
+            .collect::<Result<Vec<_>>>()?;
+        tags.extend_from_slice(extra_tags);
+
+        // Built by hand, as the client offers no way to tag deletions
+        let event =
+            EventBuilder::new(Kind::EventDeletion, reason, &tags).to_event(&self.client.keys())?;
+
+        Ok(self
+            .client
+            .send_event(event)
+            .time_as("nostr.delete.client_send_event")
+            .await?)
+    }
+
+    pub async fn update_user_profile(
+        &self,
+        profile: Profile,
+        config: &ProfileConfig,
+        extra_tags: &[Tag],
+    ) -> Result<EventId> {
+        let (display_name, about, nip05) = config.fill(&profile)?;
+        let mut tags = emoji_tags(&profile.emojis, &[&display_name, &about]);
+        tags.extend_from_slice(extra_tags);
+        let metadata = profile_metadata(&profile, display_name, about, nip05)?;
+
+        // Built by hand, as the client offers no way to tag metadata events
+        let event =
+            EventBuilder::new(Kind::Metadata, metadata, &tags).to_event(&self.client.keys())?;
+
+        Ok(self
+            .client
+            .send_event(event)
             .time_as("nostr.update_profile")
             .await?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::postgres::job_queue::tests::post;
+
+    const RELAY: &str = "wss://relay.example";
+
+    /// Mirrors a status by a new user, returning the id of its note.
+    async fn mirror(pool: &PgPool, uri: &str, in_reply_to: Option<&str>) -> EventId {
+        let keys = Keys::generate();
+        let note_id = EventId::from_slice(&keys.public_key().serialize()).unwrap();
+
+        let user_id: Uuid = sqlx::query_scalar(
+            "insert into users (instance_id, nostr_public_key, nostr_private_key, mastodon_user)
+            values ($1, $2, '', $3) returning id",
+        )
+        .bind(Uuid::nil())
+        .bind(keys.public_key().to_bech32().unwrap())
+        .bind(uri)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "insert into mastodon_posts
+                (instance_id, user_id, mastodon_id, mastodon_uri, in_reply_to, nostr_id, status)
+            values ($1, $2, $3, $3, $4, $5, 'posted')",
+        )
+        .bind(Uuid::nil())
+        .bind(user_id)
+        .bind(uri)
+        .bind(in_reply_to)
+        .bind(note_id.to_bech32().unwrap())
+        .execute(pool)
+        .await
+        .unwrap();
+
+        note_id
+    }
+
+    /// Builds the note of a status replying to `in_reply_to`.
+    async fn build(pool: &PgPool, in_reply_to: Option<&str>) -> Note {
+        sqlx::query("insert into nostr_relays (url) values ($1)")
+            .bind(RELAY)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let mut item = post("reply");
+        item.in_reply_to = in_reply_to.map(str::to_string);
+
+        Note::build(&Postgres::with_pool(pool.clone()), &item)
+            .await
+            .unwrap()
+    }
+
+    fn event_tags(note: &Note) -> Vec<(EventId, Option<Marker>)> {
+        note.tags
+            .iter()
+            .filter_map(|tag| match tag {
+                Tag::Event(id, relay, marker) => {
+                    assert_eq!(relay.as_deref(), Some(RELAY));
+                    Some((*id, marker.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn does_not_thread_statuses_that_reply_to_nothing(pool: PgPool) {
+        let note = build(&pool, None).await;
+
+        assert!(event_tags(&note).is_empty());
+    }
+
+    #[sqlx::test]
+    async fn marks_the_root_of_direct_replies(pool: PgPool) {
+        let root = mirror(&pool, "root", None).await;
+
+        let note = build(&pool, Some("root")).await;
+
+        assert_eq!(event_tags(&note), vec![(root, Some(Marker::Root))]);
+    }
+
+    #[sqlx::test]
+    async fn marks_the_root_and_parent_of_nested_replies(pool: PgPool) {
+        let root = mirror(&pool, "root", None).await;
+        mirror(&pool, "middle", Some("root")).await;
+        let parent = mirror(&pool, "parent", Some("middle")).await;
+
+        let note = build(&pool, Some("parent")).await;
+
+        assert_eq!(
+            event_tags(&note),
+            vec![(root, Some(Marker::Root)), (parent, Some(Marker::Reply))]
+        );
+    }
+
+    #[sqlx::test]
+    async fn marks_the_parent_when_the_root_was_not_mirrored(pool: PgPool) {
+        let parent = mirror(&pool, "parent", Some("missing")).await;
+
+        let note = build(&pool, Some("parent")).await;
+
+        assert_eq!(event_tags(&note), vec![(parent, Some(Marker::Reply))]);
+    }
+
+    fn profile(acct: &str, nip05: &str) -> Profile {
+        Profile {
+            instance_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            acct: acct.to_string(),
+            name: "alice".to_string(),
+            display_name: "Alice".to_string(),
+            about: "<p>Hi</p>".to_string(),
+            picture: String::new(),
+            nip05: nip05.to_string(),
+            banner: String::new(),
+            emojis: vec![],
+            website: "https://example.social/@alice".to_string(),
+            fields: vec![],
+            bot: false,
+        }
+    }
+
+    #[test]
+    fn fills_the_nip05_domain_of_each_instance() {
+        let config = ProfileConfig {
+            nip05_domain: "{instance}.mirror.example".to_string(),
+            display_name_template: "{display_name} ({acct})".to_string(),
+            about_template: "{about}".to_string(),
+        };
+
+        let (display_name, _, nip05) = config
+            .fill(&profile("alice@example.social", "alice_at_example.social"))
+            .unwrap();
+
+        assert_eq!(display_name, "Alice (alice@example.social)");
+        assert_eq!(
+            nip05,
+            "alice_at_example.social@example.social.mirror.example"
+        );
+
+        let (_, _, nip05) = config
+            .fill(&profile("bob@other.social", "bob_at_other.social"))
+            .unwrap();
+
+        assert_eq!(nip05, "bob_at_other.social@other.social.mirror.example");
+    }
+
+    #[test]
+    fn renders_the_configuration_into_the_metadata() {
+        let mut config = ProfileConfig {
+            nip05_domain: "mirror.example".to_string(),
+            display_name_template: "{display_name}".to_string(),
+            about_template: "{about}".to_string(),
+        };
+        let profile = profile("alice@example.social", "alice_at_example.social");
+
+        let metadata = config.metadata(&profile).unwrap();
+
+        assert!(metadata.contains("alice_at_example.social@mirror.example"));
+
+        config.nip05_domain = "other.example".to_string();
+
+        assert_ne!(config.metadata(&profile).unwrap(), metadata);
+    }
+}
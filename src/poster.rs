@@ -1,56 +1,586 @@
-use eyre::{ErrReport, Result};
-use metrics::increment_counter;
-use tracing::error;
+use std::collections::HashMap;
+
+use clap::{Parser, ValueEnum};
+use eyre::{eyre, ErrReport, Result};
+use mastodon_async::prelude::StatusId;
+use metrics::{gauge, increment_counter};
+use nostr_sdk::prelude::{EventId, FromBech32, Tag, TagKind, Timestamp};
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info, warn};
 
 use crate::{
     health::*,
-    nostr::{Nostr, Note},
-    postgres::{job_queue::ScheduledPost, *},
+    listener::prepare_status,
+    mastodon::{Mastodon, MastodonClient},
+    nostr::{proxy_tag, Nostr, Note, Poll, ProfileConfig},
+    postgres::{
+        job_queue::{Job, RetryConfig, ScheduledPost, ScheduledPostKind},
+        *,
+    },
+    util::{actor_uri, fill_template},
 };
 
-async fn process_item(postgres: Postgres, item: ScheduledPost) -> Result<()> {
+/// How a new version of an already mirrored status is published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EditMode {
+    /// Delete the previous note and publish the new version
+    Replace,
+    /// Keep the previous note, publishing the new version quoting it (NIP-18).
+    /// Nostr has no way to mark a note as an edit of another, so clients show
+    /// both
+    Revision,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct PosterConfig {
+    #[clap(
+        long = "edit-mode",
+        env = "NOSTODON_EDIT_MODE",
+        value_enum,
+        default_value = "replace"
+    )]
+    /// How edits to Mastodon statuses are mirrored
+    pub edit_mode: EditMode,
+
+    #[clap(
+        long = "max-ancestors",
+        env = "NOSTODON_MAX_ANCESTORS",
+        default_value_t = 20
+    )]
+    /// Maximum number of missing ancestors mirrored to complete a thread
+    pub max_ancestors: usize,
+
+    #[clap(
+        long = "max-backdate-hours",
+        env = "NOSTODON_MAX_BACKDATE_HOURS",
+        default_value_t = 168
+    )]
+    /// How far in the past events can be dated. Older statuses are dated this
+    /// far back, so that they still come before the replies to them
+    pub max_backdate_hours: u64,
+
+    #[clap(long = "no-proxy-tags", env = "NOSTODON_NO_PROXY_TAGS")]
+    /// Do not tag mirrored events with the ActivityPub object they come from
+    pub no_proxy_tags: bool,
+
+    #[clap(long = "link-footer", env = "NOSTODON_LINK_FOOTER")]
+    /// End mirrored notes with a link to the original Mastodon status
+    pub link_footer: bool,
+
+    #[clap(
+        long = "link-footer-template",
+        env = "NOSTODON_LINK_FOOTER_TEMPLATE",
+        default_value = "Mirrored from {url}"
+    )]
+    /// Template for the link footer, with `{url}` as the original status
+    pub link_footer_template: String,
+
+    #[clap(long = "poll-events", env = "NOSTODON_POLL_EVENTS")]
+    /// Also publish Mastodon polls as NIP-88 poll events
+    pub poll_events: bool,
+
+    #[clap(flatten)]
+    pub profile: ProfileConfig,
+
+    #[clap(flatten)]
+    pub retry: RetryConfig,
+}
+
+impl PosterConfig {
+    /// Dates events with when the status was written, clamped to how far in
+    /// the past we are willing to backdate. Dating old statuses as of now
+    /// instead would put them after their replies.
+    fn created_at(&self, written_at: Option<OffsetDateTime>) -> Option<Timestamp> {
+        let written_at = written_at?;
+        let now = OffsetDateTime::now_utc();
+
+        if written_at > now {
+            return None;
+        }
+
+        let oldest = now - Duration::hours(self.max_backdate_hours as i64);
+
+        Some(Timestamp::from(
+            written_at.max(oldest).unix_timestamp() as u64
+        ))
+    }
+
+    /// Tags mirrored events with the ActivityPub object they came from
+    /// (NIP-48).
+    fn proxy_tags(&self, id: &str) -> Vec<Tag> {
+        match self.no_proxy_tags || id.is_empty() {
+            true => vec![],
+            false => vec![proxy_tag(id)],
+        }
+    }
+
+    /// Prepares a note built from a scheduled status for publishing.
+    fn finish_note(&self, note: &mut Note, item: &ScheduledPost) {
+        note.tags.extend(self.proxy_tags(&item.mastodon_uri));
+
+        if let (true, Some(url)) = (self.link_footer, &item.mastodon_url) {
+            let footer = fill_template(&self.link_footer_template, &[("url", url)]);
+            note.text = format!("{}\n\n{footer}", note.text);
+        }
+    }
+}
+
+/// Mirrors the ancestors of a reply that never reached us through the
+/// timeline, oldest first, so that the reply can be threaded. The parent of
+/// the reply is resolved from them if it was not known when it was scheduled.
+/// An ancestor that fails is left to be retried as a job of its own.
+async fn complete_thread(
+    postgres: Postgres,
+    config: &PosterConfig,
+    item: &mut ScheduledPost,
+) -> Result<()> {
+    let (parent_id, server_id) = match (&item.in_reply_to_id, item.server_id) {
+        (Some(parent_id), Some(server_id)) => (parent_id.clone(), server_id),
+        _ => return Ok(()),
+    };
+
+    if let Some(parent_uri) = &item.in_reply_to {
+        if postgres.fetch_post(parent_uri.clone()).await?.is_some() {
+            return Ok(());
+        }
+    }
+
+    let server = postgres.fetch_server(server_id).await?;
+    let mastodon = Mastodon::connect(&server, postgres.clone())?;
+
+    let ancestors = mastodon
+        .fetch_ancestors(&StatusId::new(&item.mastodon_id))
+        .await?;
+    let skip = ancestors.len().saturating_sub(config.max_ancestors);
+
+    // Ancestors reply to each other, so their parents are all in the context
+    let uris: HashMap<String, String> = ancestors
+        .iter()
+        .map(|status| (status.id.to_string(), status.uri.clone()))
+        .collect();
+
+    if item.in_reply_to.is_none() {
+        item.in_reply_to = uris.get(&parent_id).cloned();
+
+        // Deleted and private statuses are left out of the context
+        if item.in_reply_to.is_none() {
+            warn!(
+                uri = item.mastodon_uri,
+                parent_id, "Could not find the parent of a reply, mirroring it unthreaded"
+            );
+        }
+    }
+
+    let queue = postgres.listener();
+    let mut mirrored = 0;
+
+    for status in ancestors.into_iter().skip(skip) {
+        if postgres.fetch_post(status.uri.clone()).await?.is_some() {
+            continue;
+        }
+
+        let in_reply_to = status
+            .in_reply_to_id
+            .as_ref()
+            .and_then(|id| uris.get(id).cloned());
+
+        let post = match prepare_status(&postgres, &server, status, None, in_reply_to).await? {
+            Some(post) => post,
+            None => continue,
+        };
+
+        let lease = match queue.claim(post.clone()).await? {
+            Some(lease) => lease,
+            None => continue,
+        };
+
+        let result = queue
+            .keep_leased(
+                post.mastodon_uri.clone(),
+                lease,
+                process_item(postgres.clone(), config, post.clone()),
+            )
+            .await;
+
+        match result {
+            Ok(_) => {
+                queue.finish(post.mastodon_uri, lease).await?;
+                mirrored += 1;
+            }
+            Err(e) => {
+                warn!(error = %e, uri = post.mastodon_uri, "Could not mirror an ancestor");
+                queue
+                    .error(post.mastodon_uri, lease, e.to_string(), &config.retry)
+                    .await?;
+            }
+        }
+    }
+
+    if mirrored > 0 {
+        increment_counter!(THREADS_COMPLETED);
+    }
+
+    Ok(())
+}
+
+/// Publishes the profile of the author of a job if it changed. It is only
+/// recorded once published, so that a failure is retried along with the job.
+async fn sync_profile(
+    postgres: &Postgres,
+    nostr: &Nostr,
+    config: &PosterConfig,
+    item: &ScheduledPost,
+) -> Result<()> {
+    let profile: Profile = item.clone().into();
+    let metadata = config.profile.metadata(&profile)?;
+
+    if postgres.is_profile_changed(&profile, &metadata).await? {
+        // The profile stands for the actor, not for its page on the web
+        let actor = actor_uri(&item.mastodon_uri).unwrap_or_default();
+
+        nostr
+            .update_user_profile(profile.clone(), &config.profile, &config.proxy_tags(&actor))
+            .await?;
+
+        // Another job of the same user might have published it meanwhile
+        if postgres
+            .update_profile(&profile, &metadata)
+            .await?
+            .changed()
+        {
+            increment_counter!(PROFILES_UPDATED);
+        }
+    }
+
+    Ok(())
+}
+
+/// Mirrors a status as a note, and its poll as a NIP-88 event. Each step is
+/// skipped if an earlier attempt at the job already got through it, so that a
+/// retry does not publish the note twice.
+async fn process_item(
+    postgres: Postgres,
+    config: &PosterConfig,
+    item: ScheduledPost,
+) -> Result<()> {
     let creds = postgres.fetch_credentials(item.user_id).await?;
     let nostr = Nostr::connect(&postgres, creds).await?;
 
-    let profile: Profile = item.clone().into();
-    if postgres.update_profile(&profile).await?.changed() {
-        nostr.update_user_profile(profile).await?;
-        increment_counter!(PROFILES_UPDATED);
+    sync_profile(&postgres, &nostr, config, &item).await?;
+
+    let created_at = config.created_at(item.status_created_at);
+
+    let post = match postgres.fetch_post(item.mastodon_uri.clone()).await? {
+        Some(post) => post,
+        None => {
+            let mut note = Note::build(&postgres, &item).await?;
+            note.created_at = created_at;
+            config.finish_note(&mut note, &item);
+
+            let event_id = nostr.publish(note).await?;
+
+            let post = MastodonPost {
+                instance_id: item.instance_id,
+                user_id: item.user_id,
+                mastodon_id: item.mastodon_id.clone(),
+                mastodon_uri: item.mastodon_uri.clone(),
+                in_reply_to: item.in_reply_to.clone(),
+                nostr_id: event_id.to_string(),
+                status: MastodonPostStatus::Posted,
+                poll_nostr_id: None,
+            };
+
+            postgres.add_post(post.clone()).await?;
+            increment_counter!(POSTS_CREATED);
+
+            post
+        }
+    };
+
+    postgres
+        .add_post_version(
+            item.mastodon_uri.clone(),
+            item.edited_at,
+            post.nostr_id.clone(),
+        )
+        .await?;
+
+    if post.poll_nostr_id.is_none() {
+        publish_poll(
+            &postgres,
+            &nostr,
+            config,
+            &item,
+            EventId::from_bech32(&post.nostr_id)?,
+            created_at,
+        )
+        .await?;
     }
 
-    let note = Note::build(&postgres, &item).await?;
+    Ok(())
+}
 
-    let event_id = nostr.publish(note).await?;
+/// Publishes the poll of a status as a NIP-88 event referencing the note it
+/// was mirrored as, if the status has a poll and poll events are enabled.
+async fn publish_poll(
+    postgres: &Postgres,
+    nostr: &Nostr,
+    config: &PosterConfig,
+    item: &ScheduledPost,
+    note_id: EventId,
+    created_at: Option<Timestamp>,
+) -> Result<()> {
+    if !config.poll_events {
+        return Ok(());
+    }
+
+    let mut poll = match Poll::build(item, note_id)? {
+        Some(poll) => poll,
+        None => return Ok(()),
+    };
+
+    poll.tags.extend(config.proxy_tags(&item.mastodon_uri));
+    poll.created_at = created_at;
+
+    let poll_id = nostr.publish_poll(poll).await?;
+
+    postgres
+        .update_post_poll(item.mastodon_uri.clone(), Some(poll_id.to_string()))
+        .await?;
+
+    Ok(())
+}
+
+async fn process_deletion(
+    postgres: Postgres,
+    config: &PosterConfig,
+    item: ScheduledPost,
+) -> Result<()> {
+    let post = match postgres.fetch_post(item.mastodon_uri.clone()).await? {
+        Some(post) if post.status == MastodonPostStatus::Posted => post,
+        // Deleted before it was ever mirrored, or already deleted
+        _ => return Ok(()),
+    };
+
+    // The deletion has to be signed by the same keys as the original event
+    let creds = postgres.fetch_credentials(post.user_id).await?;
+    let nostr = Nostr::connect(&postgres, creds).await?;
+
+    let mut events = postgres
+        .fetch_post_versions(item.mastodon_uri.clone())
+        .await?;
+    events.extend(post.poll_nostr_id);
+
+    nostr
+        .delete(
+            &events,
+            "The original Mastodon post was deleted",
+            &config.proxy_tags(&item.mastodon_uri),
+        )
+        .await?;
+    postgres.mark_post_deleted(item.mastodon_uri).await?;
+
+    increment_counter!(POSTS_DELETED);
+
+    Ok(())
+}
+
+async fn process_repost(
+    postgres: Postgres,
+    config: &PosterConfig,
+    item: ScheduledPost,
+) -> Result<()> {
+    let reblog_of = item
+        .reblog_of
+        .clone()
+        .ok_or_else(|| eyre!("repost is missing the boosted status"))?;
+
+    let original = match postgres.fetch_post(reblog_of).await? {
+        Some(post) if post.status == MastodonPostStatus::Posted => post,
+        Some(_) => return Ok(()),
+        None => return Err(eyre!("boosted status has not been mirrored yet")),
+    };
+    let original_author = postgres.fetch_credentials(original.user_id).await?;
+
+    let creds = postgres.fetch_credentials(item.user_id).await?;
+    let nostr = Nostr::connect(&postgres, creds).await?;
+
+    sync_profile(&postgres, &nostr, config, &item).await?;
+
+    // Same hint as for replies, see `Note::build`
+    let relay = postgres.fetch_nostr_relays().await?.into_iter().next();
+
+    let event_id = nostr
+        .repost(
+            &original.nostr_id,
+            original_author.public_key(),
+            relay,
+            config.created_at(item.status_created_at),
+            &config.proxy_tags(&item.mastodon_uri),
+        )
+        .await?;
 
     let post = MastodonPost {
         instance_id: item.instance_id,
         user_id: item.user_id,
         mastodon_id: item.mastodon_id.clone(),
+        mastodon_uri: item.mastodon_uri.clone(),
+        in_reply_to: item.in_reply_to.clone(),
         nostr_id: event_id.to_string(),
         status: MastodonPostStatus::Posted,
+        poll_nostr_id: None,
     };
 
     postgres.add_post(post).await?;
-    postgres.listener().finish(item.mastodon_id).await?;
+    postgres
+        .add_post_version(item.mastodon_uri, None, event_id.to_string())
+        .await?;
+
+    increment_counter!(POSTS_REPOSTED);
+
+    Ok(())
+}
+
+async fn process_edit(
+    postgres: Postgres,
+    config: &PosterConfig,
+    item: ScheduledPost,
+) -> Result<()> {
+    let previous = match postgres.fetch_post(item.mastodon_uri.clone()).await? {
+        Some(post) if post.status == MastodonPostStatus::Posted => post,
+        Some(_) => return Ok(()),
+        // The original version never made it to Nostr, so there is nothing to
+        // replace.
+        None => return process_item(postgres, config, item).await,
+    };
+
+    let creds = postgres.fetch_credentials(previous.user_id).await?;
+    let nostr = Nostr::connect(&postgres, creds).await?;
+
+    sync_profile(&postgres, &nostr, config, &item).await?;
+
+    let created_at = config.created_at(item.edited_at.or(item.status_created_at));
+
+    let mut note = Note::build(&postgres, &item).await?;
+    note.created_at = created_at;
+    config.finish_note(&mut note, &item);
 
-    increment_counter!(POSTS_CREATED);
+    match config.edit_mode {
+        EditMode::Replace => {
+            let mut events = vec![previous.nostr_id.clone()];
+            events.extend(previous.poll_nostr_id.clone());
+
+            nostr
+                .delete(
+                    &events,
+                    "The original Mastodon post was edited",
+                    &config.proxy_tags(&item.mastodon_uri),
+                )
+                .await?;
+        }
+        EditMode::Revision => {
+            note.tags.push(Tag::Generic(
+                TagKind::Custom("q".to_string()),
+                vec![EventId::from_bech32(&previous.nostr_id)?.to_hex()],
+            ));
+        }
+    }
+
+    let event_id = nostr.publish(note).await?;
+
+    postgres
+        .update_post(item.mastodon_uri.clone(), event_id.to_string())
+        .await?;
+    postgres
+        .add_post_version(
+            item.mastodon_uri.clone(),
+            item.edited_at,
+            event_id.to_string(),
+        )
+        .await?;
+
+    // The poll of the previous version points to the previous note. Revisions
+    // keep it, as a version so that it goes away with the status.
+    if let Some(poll_id) = previous.poll_nostr_id {
+        postgres
+            .add_post_version(item.mastodon_uri.clone(), None, poll_id)
+            .await?;
+        postgres
+            .update_post_poll(item.mastodon_uri.clone(), None)
+            .await?;
+    }
+    publish_poll(&postgres, &nostr, config, &item, event_id, created_at).await?;
+
+    increment_counter!(POSTS_EDITED);
 
     Ok(())
 }
 
-pub async fn spawn(postgres: Postgres) -> Result<()> {
-    let mut stream = postgres.listener().update_stream().await?;
+pub async fn spawn(postgres: Postgres, config: PosterConfig) -> Result<()> {
+    let queue = postgres.listener();
+    let jobs = queue.update_stream(&config.retry).await?;
+
+    info!(worker = queue.worker_id(), "Waiting for jobs");
+    gauge!(WORKER_INFO, 1.0, "worker" => queue.worker_id().to_string());
 
-    let task = |postgres: Postgres, item: ScheduledPost| async move {
-        match process_item(postgres.clone(), item.clone()).await {
+    let task = |postgres: Postgres, config: PosterConfig, job: Job| async move {
+        let Job { lease, post: item } = job;
+
+        let work = async {
+            match item.kind {
+                ScheduledPostKind::Post => {
+                    let mut item = item.clone();
+
+                    if let Err(e) = complete_thread(postgres.clone(), &config, &mut item).await {
+                        warn!(error = %e, uri = item.mastodon_uri, "Could not complete the thread of a reply");
+                    }
+
+                    process_item(postgres.clone(), &config, item).await
+                }
+                ScheduledPostKind::Deletion => {
+                    process_deletion(postgres.clone(), &config, item.clone()).await
+                }
+                ScheduledPostKind::Edit => {
+                    let mut item = item.clone();
+
+                    if let Err(e) = complete_thread(postgres.clone(), &config, &mut item).await {
+                        warn!(error = %e, uri = item.mastodon_uri, "Could not complete the thread of a reply");
+                    }
+
+                    process_edit(postgres.clone(), &config, item).await
+                }
+                ScheduledPostKind::Repost => {
+                    process_repost(postgres.clone(), &config, item.clone()).await
+                }
+            }
+        };
+
+        let queue = postgres.listener();
+        let result = queue
+            .keep_leased(item.mastodon_uri.clone(), lease, work)
+            .await;
+
+        match result {
             Ok(_) => {
-                postgres.listener().finish(item.mastodon_id).await?;
+                queue.finish(item.mastodon_uri, lease).await?;
             }
             Err(e) => {
-                postgres
-                    .listener()
-                    .error(item.mastodon_id, e.to_string())
+                let dead = queue
+                    .error(
+                        item.mastodon_uri.clone(),
+                        lease,
+                        e.to_string(),
+                        &config.retry,
+                    )
                     .await?;
+
+                if dead {
+                    error!(error = %e, uri = item.mastodon_uri, "Giving up on job after its last attempt");
+                    increment_counter!(JOBS_DEAD);
+                } else {
+                    warn!(error = %e, uri = item.mastodon_uri, "Job failed, retrying later");
+                    increment_counter!(JOBS_RETRIED);
+                }
             }
         }
 
@@ -58,20 +588,51 @@ pub async fn spawn(postgres: Postgres) -> Result<()> {
     };
 
     loop {
-        let item = match stream.recv().await {
-            Ok(item) => item,
-            Err(_) => continue,
-        };
+        let job = jobs.next().await;
 
-        match task(postgres.clone(), item).await {
-            Ok(_) => continue,
-            Err(e) => {
-                error!(error = %e, "Error while receiving item from the stream");
-                continue;
-            }
+        if let Err(e) = task(postgres.clone(), config.clone(), job).await {
+            error!(error = %e, "Error while running a job");
         }
     }
+}
 
-    #[allow(unreachable_code)]
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PosterConfig {
+        PosterConfig::parse_from(["nostodon", "--max-backdate-hours", "24"])
+    }
+
+    fn age(timestamp: Option<Timestamp>) -> Duration {
+        let timestamp = timestamp.unwrap().as_i64();
+
+        OffsetDateTime::now_utc() - OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+    }
+
+    #[test]
+    fn dates_events_as_of_their_status() {
+        let written_at = OffsetDateTime::now_utc() - Duration::hours(23);
+
+        let created_at = config().created_at(Some(written_at)).unwrap();
+
+        assert_eq!(created_at.as_i64(), written_at.unix_timestamp());
+    }
+
+    #[test]
+    fn clamps_old_statuses_to_the_oldest_date() {
+        let created_at = config().created_at(Some(OffsetDateTime::now_utc() - Duration::hours(25)));
+
+        assert!((age(created_at) - Duration::hours(24)).abs() < Duration::minutes(1));
+    }
+
+    #[test]
+    fn dates_future_and_unknown_statuses_as_of_now() {
+        let config = config();
+
+        assert!(config
+            .created_at(Some(OffsetDateTime::now_utc() + Duration::hours(1)))
+            .is_none());
+        assert!(config.created_at(None).is_none());
+    }
 }
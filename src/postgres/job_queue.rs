@@ -1,9 +1,26 @@
-use eyre::{ErrReport, Result};
-use sqlx::{postgres::PgListener, Pool, Postgres};
-use tokio::sync::broadcast::{self, Receiver, Sender};
+use clap::Parser;
+use eyre::{eyre, Result};
+use metrics::{counter, increment_counter};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, types::Json, Pool, Postgres};
+use std::{future::Future, sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::{sync::Notify, time::sleep};
+use tracing::{error, warn};
 use uuid::Uuid;
 
-use crate::health::Timeable;
+use crate::health::{set_ready, Timeable, JOBS_DEAD, JOBS_RECLAIMED};
+
+/// Name the job queue listener reports its readiness under
+const JOB_QUEUE_COMPONENT: &str = "job_queue";
+const LISTEN_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the queue is polled for jobs that came due without a notification
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a claimed job stays ours without a heartbeat
+const LEASE_DURATION: Duration = Duration::from_secs(120);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// How often jobs with expired leases are handed out again
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone, sqlx::Type)]
 #[sqlx(type_name = "scheduled_post_status")]
@@ -11,83 +28,394 @@ use crate::health::Timeable;
 pub enum ScheduledPostStatus {
     New,
     Running,
-    Errored,
+    /// Failed on every attempt it got
+    Dead,
     Finished,
 }
 
+/// How failed jobs are retried.
+#[derive(Debug, Clone, Parser)]
+pub struct RetryConfig {
+    #[clap(
+        long = "max-attempts",
+        env = "NOSTODON_MAX_ATTEMPTS",
+        default_value_t = 8
+    )]
+    /// Attempts a job gets before it is left as a dead letter
+    pub max_attempts: i32,
+
+    #[clap(
+        long = "retry-delay-secs",
+        env = "NOSTODON_RETRY_DELAY_SECS",
+        default_value_t = 30
+    )]
+    /// Delay before retrying a failed job, doubling with every attempt
+    pub retry_delay_secs: u64,
+
+    #[clap(
+        long = "max-retry-delay-secs",
+        env = "NOSTODON_MAX_RETRY_DELAY_SECS",
+        default_value_t = 21600
+    )]
+    /// Longest delay between two attempts at a job
+    pub max_retry_delay_secs: u64,
+}
+
+/// What the poster should do with a scheduled post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "scheduled_post_kind")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ScheduledPostKind {
+    /// Mirror the status to Nostr
+    Post,
+    /// Remove the mirrored status from Nostr
+    Deletion,
+    /// Replace the mirrored status with a newer version
+    Edit,
+    /// Mirror a boost of another status
+    Repost,
+}
+
+/// A media attachment of a scheduled status, with the metadata needed for its
+/// NIP-92 `imeta` tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub url: String,
+    pub mime_type: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub blurhash: Option<String>,
+    pub description: Option<String>,
+}
+
+/// A custom emoji, referenced by its `:shortcode:` in text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Emoji {
+    pub shortcode: String,
+    pub url: String,
+}
+
+/// A verified field of a profile, with its value as html.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileField {
+    pub name: String,
+    pub value: String,
+}
+
+/// An account mentioned in a scheduled status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mention {
+    /// Full `username@domain` handle
+    pub acct: String,
+    /// Profile url the status links to
+    pub url: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ScheduledPost {
+    pub kind: ScheduledPostKind,
     pub user_id: Uuid,
     pub instance_id: Uuid,
+    pub server_id: Option<Uuid>,
     pub mastodon_id: String,
+    pub mastodon_uri: String,
+    /// Link to the status on its own server
+    pub mastodon_url: Option<String>,
+    /// When the status was posted on Mastodon
+    pub status_created_at: Option<OffsetDateTime>,
+    pub edited_at: Option<OffsetDateTime>,
+    /// URI of the status this replies to, if it was known when scheduling
     pub in_reply_to: Option<String>,
+    /// Id of the status this replies to on the server that delivered it
+    pub in_reply_to_id: Option<String>,
+    pub reblog_of: Option<String>,
+    /// URI of a status whose job has to be done before this one can run
+    pub depends_on: Option<String>,
     pub content: String,
+    /// Content warning shown instead of the status until it is expanded
+    pub spoiler_text: String,
+    pub sensitive: bool,
+    /// ISO 639-1 code of the language of the status, or its ISO 639-3 code
+    /// when it has no two letter one
+    pub language: Option<String>,
+    pub attachments: Json<Vec<Attachment>>,
+    /// Lowercase names of the hashtags in the status
+    pub hashtags: Vec<String>,
+    pub mentions: Json<Vec<Mention>>,
+    pub emojis: Json<Vec<Emoji>>,
+    /// Options of the poll in the status, if it has one
+    pub poll_options: Json<Vec<String>>,
+    pub poll_multiple: bool,
+    pub poll_expires_at: Option<OffsetDateTime>,
+    /// Full `username@domain` handle of the author
+    pub profile_acct: String,
     pub profile_name: String,
     pub profile_display_name: String,
     pub profile_about: String,
     pub profile_picture: String,
     pub profile_nip05: String,
     pub profile_banner: String,
+    pub profile_emojis: Json<Vec<Emoji>>,
+    /// Url of the profile page on Mastodon
+    pub profile_website: String,
+    pub profile_fields: Json<Vec<ProfileField>>,
+    pub profile_bot: bool,
+}
+
+/// A job leased to us, along with the lease that has to be held to work on it.
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// Identifies this attempt at the job, so that nobody else can finish it
+    pub lease: Uuid,
+    pub post: ScheduledPost,
+}
+
+/// A job whose lease expired, and the worker that held it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReclaimedJob {
+    pub mastodon_uri: String,
+    pub worker_id: Option<String>,
+    /// Whether the job was left as a dead letter
+    pub dead: bool,
+}
+
+/// Identifies this process on the jobs it leases. It stays the same for as
+/// long as the process runs, unlike the lease on each job.
+pub fn worker_id() -> String {
+    format!("{}:{}", whoami::hostname(), std::process::id())
 }
 
 pub struct JobQueue {
     pool: Pool<Postgres>,
-    sender: Sender<ScheduledPost>,
-    _receiver: Receiver<ScheduledPost>,
+    worker_id: String,
 }
 
-async fn poll_job(pool: &Pool<Postgres>) -> Result<Option<ScheduledPost>> {
-    Ok(sqlx::query_as!(
+/// Leases the next job that is due to a worker, if any.
+async fn poll_job(pool: &Pool<Postgres>, worker_id: &str) -> Result<Option<Job>> {
+    let lease = Uuid::new_v4();
+
+    let post = sqlx::query_as!(
         ScheduledPost,
         r#"
-             update scheduled_posts set status = 'running'
+             update scheduled_posts set
+                status = 'running',
+                leased_by = $1,
+                lease_expires_at = now() + make_interval(secs => $2),
+                worker_id = $3
              where id = (
-                select id from scheduled_posts where status = 'new'
+                select id from scheduled_posts p where status = 'new' and run_at <= now()
+                and not exists (
+                    select 1 from scheduled_posts d
+                    where d.mastodon_uri = p.depends_on and d.status in ('new', 'running')
+                )
                 order by id
                 for update skip locked
                 limit 1
-             ) returning 
-                user_id, instance_id, mastodon_id, in_reply_to, content,
-                profile_name, profile_display_name, profile_about,
-                profile_picture, profile_nip05, profile_banner
-            "#
+             ) returning
+                kind as "kind: ScheduledPostKind", user_id, instance_id, server_id, mastodon_id, mastodon_uri, mastodon_url, status_created_at,
+                edited_at, in_reply_to, in_reply_to_id, reblog_of, depends_on,
+                content, spoiler_text, sensitive, language,
+                attachments as "attachments: Json<Vec<Attachment>>", hashtags,
+                mentions as "mentions: Json<Vec<Mention>>",
+                emojis as "emojis: Json<Vec<Emoji>>",
+                poll_options as "poll_options: Json<Vec<String>>", poll_multiple,
+                poll_expires_at, profile_acct, profile_name, profile_display_name, profile_about,
+                profile_picture, profile_nip05, profile_banner,
+                profile_emojis as "profile_emojis: Json<Vec<Emoji>>", profile_website,
+                profile_fields as "profile_fields: Json<Vec<ProfileField>>", profile_bot
+            "#,
+        lease,
+        LEASE_DURATION.as_secs_f64(),
+        worker_id,
     )
     .fetch_optional(pool)
     .time_as("postgres.job_queue.poll_job")
-    .await?)
+    .await?;
+
+    Ok(post.map(|post| Job { lease, post }))
+}
+
+async fn listen(pool: &Pool<Postgres>) -> Result<PgListener> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen("scheduled_posts_status_channel").await?;
+
+    Ok(listener)
+}
+
+/// Wakes up whoever is waiting for jobs whenever the queue changes, listening
+/// again with a new connection whenever the current one is lost.
+async fn watch_jobs(pool: &Pool<Postgres>, notify: &Notify) {
+    loop {
+        let mut listener = match listen(pool).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!(error = %e, "Could not listen for scheduled posts");
+                sleep(LISTEN_RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+
+        set_ready(JOB_QUEUE_COMPONENT, true);
+
+        // Jobs might have been scheduled while we were not listening
+        notify.notify_one();
+
+        while let Ok(Some(_)) = listener.try_recv().time_as("postgres.job_queue.recv").await {
+            notify.notify_one();
+        }
+
+        warn!("Lost the connection listening for scheduled posts");
+        set_ready(JOB_QUEUE_COMPONENT, false);
+    }
+}
+
+/// Jobs as they come due, each leased only once it is taken.
+pub struct JobStream {
+    pool: Pool<Postgres>,
+    worker_id: String,
+    notify: Arc<Notify>,
+}
+
+impl JobStream {
+    /// Waits for the next job that is due and leases it. Errors are logged and
+    /// retried, as there is nothing the caller could do about them.
+    pub async fn next(&self) -> Job {
+        loop {
+            match poll_job(&self.pool, &self.worker_id).await {
+                Ok(Some(job)) => return job,
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(error = %e, "Could not poll for scheduled posts");
+                    sleep(LISTEN_RETRY_INTERVAL).await;
+                    continue;
+                }
+            }
+
+            // Retried jobs come due without any notification
+            tokio::select! {
+                _ = self.notify.notified() => {}
+                _ = sleep(POLL_INTERVAL) => {}
+            }
+        }
+    }
 }
 
 impl JobQueue {
     pub fn new(pool: Pool<Postgres>) -> Self {
-        let (sender, _receiver) = broadcast::channel(128);
-
         Self {
             pool,
-            sender,
-            _receiver,
+            worker_id: worker_id(),
         }
     }
 
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    /// Schedules a status to be mirrored. Scheduling a newer version of a
+    /// status we already have replaces its content, turning it into an edit
+    /// if the previous version was already picked up. A running job is left
+    /// to finish first, and queued again as an edit when it does. The job is
+    /// held back while the job of the status it depends on is still pending.
     pub async fn push(&self, post: ScheduledPost) -> Result<()> {
         sqlx::query!(
             r#"
             insert into scheduled_posts
-                (user_id, instance_id, mastodon_id, content, status,
-                 profile_name, profile_display_name, profile_about,
-                profile_picture, profile_nip05, profile_banner)
+                (kind, user_id, instance_id, server_id, mastodon_id, mastodon_uri,
+                 mastodon_url, status_created_at, edited_at, in_reply_to, reblog_of,
+                 depends_on, content, spoiler_text, sensitive, language, attachments,
+                 hashtags, mentions, emojis, poll_options, poll_multiple, poll_expires_at,
+                 status, profile_name, profile_display_name, profile_about, profile_picture,
+                 profile_nip05, profile_banner, profile_emojis, profile_website, profile_fields,
+                 profile_bot, profile_acct, in_reply_to_id)
             values
-                ($1, $2, $3, $4, 'new', $5, $6, $7, $8, $9, $10)
-            on conflict do nothing"#,
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                 $17, $18, $19, $20, $21,
+                 $22, $23, 'new', $24, $25, $26, $27, $28, $29, $30, $31,
+                 $32, $33, $34, $35)
+            on conflict (mastodon_uri) do update set
+                mastodon_url = excluded.mastodon_url,
+                edited_at = excluded.edited_at,
+                -- Edits cannot change the parent, which we might know by now
+                in_reply_to = coalesce(excluded.in_reply_to, scheduled_posts.in_reply_to),
+                in_reply_to_id = excluded.in_reply_to_id,
+                depends_on = excluded.depends_on,
+                content = excluded.content,
+                spoiler_text = excluded.spoiler_text,
+                sensitive = excluded.sensitive,
+                language = excluded.language,
+                attachments = excluded.attachments,
+                hashtags = excluded.hashtags,
+                mentions = excluded.mentions,
+                emojis = excluded.emojis,
+                poll_options = excluded.poll_options,
+                poll_multiple = excluded.poll_multiple,
+                poll_expires_at = excluded.poll_expires_at,
+                profile_name = excluded.profile_name,
+                profile_display_name = excluded.profile_display_name,
+                profile_about = excluded.profile_about,
+                profile_picture = excluded.profile_picture,
+                profile_nip05 = excluded.profile_nip05,
+                profile_banner = excluded.profile_banner,
+                profile_emojis = excluded.profile_emojis,
+                profile_website = excluded.profile_website,
+                profile_fields = excluded.profile_fields,
+                profile_bot = excluded.profile_bot,
+                profile_acct = excluded.profile_acct,
+                kind = case
+                    when scheduled_posts.status in ('new', 'running') then scheduled_posts.kind
+                    else 'edit'
+                end,
+                status = case
+                    when scheduled_posts.status = 'running' then scheduled_posts.status
+                    else 'new'
+                end,
+                attempts = case
+                    when scheduled_posts.status = 'running' then scheduled_posts.attempts
+                    else 0
+                end,
+                run_at = case
+                    when scheduled_posts.status = 'running' then scheduled_posts.run_at
+                    else now()
+                end,
+                edit_pending = scheduled_posts.status = 'running'
+            where scheduled_posts.kind <> 'deletion' and not scheduled_posts.deletion_pending
+                and excluded.edited_at > coalesce(scheduled_posts.edited_at, '-infinity')"#,
+            post.kind as ScheduledPostKind,
             post.user_id,
             post.instance_id,
+            post.server_id,
             post.mastodon_id,
+            post.mastodon_uri,
+            post.mastodon_url,
+            post.status_created_at,
+            post.edited_at,
+            post.in_reply_to,
+            post.reblog_of,
+            post.depends_on,
             post.content,
+            post.spoiler_text,
+            post.sensitive,
+            post.language,
+            post.attachments as Json<Vec<Attachment>>,
+            &post.hashtags[..],
+            post.mentions as Json<Vec<Mention>>,
+            post.emojis as Json<Vec<Emoji>>,
+            post.poll_options as Json<Vec<String>>,
+            post.poll_multiple,
+            post.poll_expires_at,
             post.profile_name,
             post.profile_display_name,
             post.profile_about,
             post.profile_picture,
             post.profile_nip05,
             post.profile_banner,
+            post.profile_emojis as Json<Vec<Emoji>>,
+            post.profile_website,
+            post.profile_fields as Json<Vec<ProfileField>>,
+            post.profile_bot,
+            post.profile_acct,
+            post.in_reply_to_id,
         )
         .execute(&self.pool)
         .time_as("postgres.job_queue.push")
@@ -96,13 +424,129 @@ impl JobQueue {
         Ok(())
     }
 
-    pub async fn finish(&self, mastodon_id: String) -> Result<()> {
+    /// Schedules a status and claims its job right away, for statuses the
+    /// poster has to mirror before it can go on. Returns the lease on the
+    /// job, or `None` if the job was already taken or done.
+    pub async fn claim(&self, post: ScheduledPost) -> Result<Option<Uuid>> {
+        let lease = Uuid::new_v4();
+
+        let result = sqlx::query!(
+            r#"
+            insert into scheduled_posts
+                (kind, user_id, instance_id, server_id, mastodon_id, mastodon_uri,
+                 mastodon_url, status_created_at, edited_at, in_reply_to, reblog_of,
+                 depends_on, content, spoiler_text, sensitive, language, attachments,
+                 hashtags, mentions, emojis, poll_options, poll_multiple, poll_expires_at,
+                 status, profile_name, profile_display_name, profile_about, profile_picture,
+                 profile_nip05, profile_banner, profile_emojis, profile_website, profile_fields,
+                 profile_bot, profile_acct, leased_by, lease_expires_at, in_reply_to_id,
+                 worker_id)
+            values
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                 $17, $18, $19, $20, $21,
+                 $22, $23, 'running', $24, $25, $26, $27, $28, $29, $30, $31,
+                 $32, $33, $34, $35, now() + make_interval(secs => $36), $37, $38)
+            on conflict (mastodon_uri) do update set
+                status = 'running',
+                leased_by = excluded.leased_by,
+                lease_expires_at = excluded.lease_expires_at,
+                worker_id = excluded.worker_id
+            where scheduled_posts.status = 'new' and scheduled_posts.kind = 'post'"#,
+            post.kind as ScheduledPostKind,
+            post.user_id,
+            post.instance_id,
+            post.server_id,
+            post.mastodon_id,
+            post.mastodon_uri,
+            post.mastodon_url,
+            post.status_created_at,
+            post.edited_at,
+            post.in_reply_to,
+            post.reblog_of,
+            post.depends_on,
+            post.content,
+            post.spoiler_text,
+            post.sensitive,
+            post.language,
+            post.attachments as Json<Vec<Attachment>>,
+            &post.hashtags[..],
+            post.mentions as Json<Vec<Mention>>,
+            post.emojis as Json<Vec<Emoji>>,
+            post.poll_options as Json<Vec<String>>,
+            post.poll_multiple,
+            post.poll_expires_at,
+            post.profile_name,
+            post.profile_display_name,
+            post.profile_about,
+            post.profile_picture,
+            post.profile_nip05,
+            post.profile_banner,
+            post.profile_emojis as Json<Vec<Emoji>>,
+            post.profile_website,
+            post.profile_fields as Json<Vec<ProfileField>>,
+            post.profile_bot,
+            post.profile_acct,
+            lease,
+            LEASE_DURATION.as_secs_f64(),
+            post.in_reply_to_id,
+            self.worker_id,
+        )
+        .execute(&self.pool)
+        .time_as("postgres.job_queue.claim")
+        .await?;
+
+        Ok((result.rows_affected() > 0).then_some(lease))
+    }
+
+    /// Turns the job for a status into a deletion job and queues it again. A
+    /// running job is left to finish first, and turned into a deletion when it
+    /// does. Returns false if the status was never scheduled.
+    pub async fn schedule_deletion(&self, mastodon_uri: String) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            update scheduled_posts set
+                kind = case when status = 'running' then kind else 'deletion' end,
+                status = case when status = 'running' then status else 'new' end,
+                attempts = case when status = 'running' then attempts else 0 end,
+                run_at = case when status = 'running' then run_at else now() end,
+                deletion_pending = status = 'running'
+            where kind <> 'deletion' and not deletion_pending and mastodon_uri = $1
+            "#,
+            mastodon_uri
+        )
+        .execute(&self.pool)
+        .time_as("postgres.job_queue.schedule_deletion")
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Marks a job as done, queueing the deletion of its status instead if
+    /// the status was deleted while the job was running, or the newer version
+    /// if it was edited.
+    pub async fn finish(&self, mastodon_uri: String, lease: Uuid) -> Result<()> {
         sqlx::query!(
             r#"
-            update scheduled_posts set status = 'finished'
-            where status = 'running' and mastodon_id = $1
+            update scheduled_posts set
+                kind = case
+                    when deletion_pending then 'deletion'
+                    when edit_pending then 'edit'
+                    else kind
+                end,
+                status = case
+                    when deletion_pending or edit_pending then 'new'::scheduled_post_status
+                    else 'finished'
+                end,
+                attempts = case when deletion_pending or edit_pending then 0 else attempts end,
+                run_at = case when deletion_pending or edit_pending then now() else run_at end,
+                deletion_pending = false,
+                edit_pending = false,
+                leased_by = null,
+                lease_expires_at = null
+            where status = 'running' and mastodon_uri = $1 and leased_by = $2
             "#,
-            mastodon_id
+            mastodon_uri,
+            lease
         )
         .execute(&self.pool)
         .time_as("postgres.job_queue.finish")
@@ -111,52 +555,624 @@ impl JobQueue {
         Ok(())
     }
 
-    pub async fn error(&self, mastodon_id: String, reason: String) -> Result<()> {
-        sqlx::query_as!(
-            super::ResultContainer,
+    /// Records a failed attempt at a job, scheduling it to be retried after
+    /// an exponential backoff with jitter. The retry picks up the newest
+    /// version of the status, but if the failed attempt already published it,
+    /// a pending edit is retried as an edit. A pending deletion replaces the
+    /// job, unless nothing was published and there is nothing to delete.
+    /// Returns true if the job ran out of attempts and was left as a dead
+    /// letter instead.
+    pub async fn error(
+        &self,
+        mastodon_uri: String,
+        lease: Uuid,
+        reason: String,
+        config: &RetryConfig,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
             r#"
-            update
-                scheduled_posts
-            set status = 'errored', fail_reason = $1
-            where status = 'running' and mastodon_id = $2
+            update scheduled_posts set
+                attempts = case when deletion_pending then 0 else attempts + 1 end,
+                last_error = $1,
+                kind = case
+                    when deletion_pending then 'deletion'
+                    when edit_pending and exists (
+                        select 1 from mastodon_posts
+                        where mastodon_posts.mastodon_uri = scheduled_posts.mastodon_uri
+                    ) then 'edit'
+                    else kind
+                end,
+                status = case
+                    when deletion_pending and not exists (
+                        select 1 from mastodon_posts
+                        where mastodon_posts.mastodon_uri = scheduled_posts.mastodon_uri
+                            and mastodon_posts.status = 'posted'
+                    ) then 'finished'::scheduled_post_status
+                    when deletion_pending then 'new'
+                    when attempts + 1 >= $3 then 'dead'
+                    else 'new'
+                end,
+                run_at = case
+                    when deletion_pending then now()
+                    else now() + scheduled_post_retry_delay(attempts, $4, $5)
+                end,
+                deletion_pending = false,
+                edit_pending = false,
+                leased_by = null,
+                lease_expires_at = null
+            where status = 'running' and mastodon_uri = $2 and leased_by = $6
+            returning status as "status: ScheduledPostStatus"
             "#,
             reason,
-            mastodon_id
+            mastodon_uri,
+            config.max_attempts,
+            config.retry_delay_secs as f64,
+            config.max_retry_delay_secs as f64,
+            lease,
+        )
+        .fetch_optional(&self.pool)
+        .time_as("postgres.job_queue.error")
+        .await?;
+
+        Ok(matches!(
+            result.map(|x| x.status),
+            Some(ScheduledPostStatus::Dead)
+        ))
+    }
+
+    /// Extends the lease on a job we are working on. Returns false if the job
+    /// is no longer ours.
+    pub async fn renew_lease(&self, mastodon_uri: String, lease: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            update scheduled_posts set lease_expires_at = now() + make_interval(secs => $3)
+            where status = 'running' and mastodon_uri = $1 and leased_by = $2
+            "#,
+            mastodon_uri,
+            lease,
+            LEASE_DURATION.as_secs_f64()
         )
         .execute(&self.pool)
-        .time_as("postgres.job_queue.finish")
+        .time_as("postgres.job_queue.renew_lease")
         .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Runs a job, renewing its lease until it is done. The job is abandoned
+    /// as soon as the lease is lost, as someone else may be running it by then.
+    pub async fn keep_leased<T, F>(&self, mastodon_uri: String, lease: Uuid, job: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let heartbeat = async {
+            loop {
+                sleep(HEARTBEAT_INTERVAL).await;
+
+                match self.renew_lease(mastodon_uri.clone(), lease).await {
+                    Ok(true) => {}
+                    Ok(false) => return Err(eyre!("lost the lease on the job")),
+                    Err(e) => {
+                        warn!(error = %e, uri = mastodon_uri, "Could not renew the lease on a job")
+                    }
+                }
+            }
+        };
+
+        tokio::select! {
+            output = job => output,
+            lost = heartbeat => lost,
+        }
+    }
+
+    /// Hands out the jobs whose lease expired again, as whoever was working on
+    /// them is gone. That counts as a failed attempt, so a job that keeps
+    /// crashing or hanging its worker ends up as a dead letter. Pending edits
+    /// and deletions are handled as in [`JobQueue::error`]. Returns the
+    /// reclaimed jobs.
+    pub async fn reap_expired_leases(&self, config: &RetryConfig) -> Result<Vec<ReclaimedJob>> {
+        let reclaimed = sqlx::query!(
+            r#"
+            update scheduled_posts set
+                attempts = case when deletion_pending then 0 else attempts + 1 end,
+                last_error = 'lease expired',
+                kind = case
+                    when deletion_pending then 'deletion'
+                    when edit_pending and exists (
+                        select 1 from mastodon_posts
+                        where mastodon_posts.mastodon_uri = scheduled_posts.mastodon_uri
+                    ) then 'edit'
+                    else kind
+                end,
+                status = case
+                    when deletion_pending and not exists (
+                        select 1 from mastodon_posts
+                        where mastodon_posts.mastodon_uri = scheduled_posts.mastodon_uri
+                            and mastodon_posts.status = 'posted'
+                    ) then 'finished'::scheduled_post_status
+                    when deletion_pending then 'new'
+                    when attempts + 1 >= $1 then 'dead'
+                    else 'new'
+                end,
+                run_at = case
+                    when deletion_pending then now()
+                    else now() + scheduled_post_retry_delay(attempts, $2, $3)
+                end,
+                deletion_pending = false,
+                edit_pending = false,
+                leased_by = null,
+                lease_expires_at = null
+            where status = 'running' and lease_expires_at < now()
+            returning mastodon_uri, worker_id, status as "status: ScheduledPostStatus"
+            "#,
+            config.max_attempts,
+            config.retry_delay_secs as f64,
+            config.max_retry_delay_secs as f64,
+        )
+        .fetch_all(&self.pool)
+        .time_as("postgres.job_queue.reap_expired_leases")
+        .await?;
+
+        Ok(reclaimed
+            .into_iter()
+            .map(|job| ReclaimedJob {
+                mastodon_uri: job.mastodon_uri,
+                worker_id: job.worker_id,
+                dead: matches!(job.status, ScheduledPostStatus::Dead),
+            })
+            .collect())
     }
 
-    pub async fn update_stream(&self) -> Result<Receiver<ScheduledPost>> {
-        let sender = self.sender.clone();
+    /// Starts watching the queue, and reclaiming the jobs of workers that
+    /// are gone.
+    pub async fn update_stream(&self, config: &RetryConfig) -> Result<JobStream> {
+        let notify = Arc::new(Notify::new());
+
         let pool = self.pool.clone();
+        let watcher = notify.clone();
 
         tokio::task::spawn(async move {
-            let sender = sender.clone();
+            watch_jobs(&pool, &watcher).await;
+        });
 
-            let mut listener = PgListener::connect_with(&pool).await?;
-            listener.listen("scheduled_posts_status_channel").await?;
+        let queue = JobQueue::new(self.pool.clone());
+        let config = config.clone();
 
+        tokio::task::spawn(async move {
             loop {
-                while listener
-                    .recv()
-                    .time_as("postgres.job_queue.recv")
-                    .await
-                    .is_ok()
-                {
-                    if let Some(job) = poll_job(&pool).await? {
-                        sender.send(job)?;
+                sleep(REAP_INTERVAL).await;
+
+                let reclaimed = match queue.reap_expired_leases(&config).await {
+                    Ok(reclaimed) => reclaimed,
+                    Err(e) => {
+                        warn!(error = %e, "Could not reclaim jobs with expired leases");
+                        continue;
                     }
+                };
+
+                if !reclaimed.is_empty() {
+                    warn!(
+                        count = reclaimed.len(),
+                        "Reclaimed jobs with expired leases"
+                    );
+                    counter!(JOBS_RECLAIMED, reclaimed.len() as u64);
                 }
-            }
 
-            #[allow(unreachable_code)]
-            Ok::<_, ErrReport>(())
+                for job in reclaimed {
+                    if job.dead {
+                        error!(
+                            uri = job.mastodon_uri,
+                            worker = job.worker_id,
+                            "Giving up on job after its lease expired on its last attempt"
+                        );
+                        increment_counter!(JOBS_DEAD);
+                    } else {
+                        warn!(
+                            uri = job.mastodon_uri,
+                            worker = job.worker_id,
+                            "Reclaimed job from a worker that is gone"
+                        );
+                    }
+                }
+            }
         });
 
-        Ok(self.sender.subscribe())
+        Ok(JobStream {
+            pool: self.pool.clone(),
+            worker_id: self.worker_id.clone(),
+            notify,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+
+    pub(crate) fn post(uri: &str) -> ScheduledPost {
+        ScheduledPost {
+            kind: ScheduledPostKind::Post,
+            user_id: Uuid::nil(),
+            instance_id: Uuid::nil(),
+            server_id: None,
+            mastodon_id: uri.to_string(),
+            mastodon_uri: uri.to_string(),
+            mastodon_url: None,
+            status_created_at: None,
+            edited_at: None,
+            in_reply_to: None,
+            in_reply_to_id: None,
+            reblog_of: None,
+            depends_on: None,
+            content: "<p>Hello</p>".to_string(),
+            spoiler_text: String::new(),
+            sensitive: false,
+            language: None,
+            attachments: Json::default(),
+            hashtags: vec![],
+            mentions: Json::default(),
+            emojis: Json::default(),
+            poll_options: Json::default(),
+            poll_multiple: false,
+            poll_expires_at: None,
+            profile_acct: "alice@example.social".to_string(),
+            profile_name: "alice".to_string(),
+            profile_display_name: "Alice".to_string(),
+            profile_about: String::new(),
+            profile_picture: String::new(),
+            profile_nip05: String::new(),
+            profile_banner: String::new(),
+            profile_emojis: Json::default(),
+            profile_website: String::new(),
+            profile_fields: Json::default(),
+            profile_bot: false,
+        }
+    }
+
+    fn retry(max_attempts: i32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            retry_delay_secs: 30,
+            max_retry_delay_secs: 60,
+        }
+    }
+
+    /// Status, kind and attempts of the job of a status, and whether a
+    /// deletion or an edit is pending on it.
+    async fn state(pool: &PgPool, uri: &str) -> (String, String, i32, bool, bool) {
+        sqlx::query_as(
+            "select status::text, kind::text, attempts, deletion_pending, edit_pending
+            from scheduled_posts where mastodon_uri = $1",
+        )
+        .bind(uri)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    /// Records a status as mirrored to Nostr.
+    async fn publish(pool: &PgPool, uri: &str) {
+        sqlx::query(
+            "insert into mastodon_posts
+                (instance_id, user_id, mastodon_id, mastodon_uri, nostr_id, status)
+            values ($1, $1, $2, $2, 'note', 'posted')",
+        )
+        .bind(Uuid::nil())
+        .bind(uri)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// Whether the job of a status is waiting for a retry.
+    async fn is_backing_off(pool: &PgPool, uri: &str) -> bool {
+        sqlx::query_scalar("select run_at > now() from scheduled_posts where mastodon_uri = $1")
+            .bind(uri)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn leases_each_job_once(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        assert_eq!(job.post.mastodon_uri, "a");
+        assert_eq!(state(&pool, "a").await.0, "running");
+        assert!(poll_job(&pool, "worker").await.unwrap().is_none());
+
+        queue.finish("a".to_string(), job.lease).await.unwrap();
+
+        assert_eq!(state(&pool, "a").await.0, "finished");
+    }
+
+    #[sqlx::test]
+    async fn holds_jobs_back_until_their_dependency_is_done(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        let mut reply = post("reply");
+        reply.depends_on = Some("parent".to_string());
+
+        queue.push(post("parent")).await.unwrap();
+        queue.push(reply).await.unwrap();
+
+        let parent = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        assert_eq!(parent.post.mastodon_uri, "parent");
+        assert!(poll_job(&pool, "worker").await.unwrap().is_none());
+
+        queue
+            .finish("parent".to_string(), parent.lease)
+            .await
+            .unwrap();
+
+        let reply = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        assert_eq!(reply.post.mastodon_uri, "reply");
+    }
+
+    #[sqlx::test]
+    async fn queues_edits_of_running_jobs_once_they_finish(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        let mut edit = post("a");
+        edit.edited_at = Some(OffsetDateTime::now_utc());
+        edit.content = "<p>Edited</p>".to_string();
+        queue.push(edit).await.unwrap();
+
+        assert_eq!(
+            state(&pool, "a").await,
+            ("running".to_string(), "post".to_string(), 0, false, true)
+        );
+
+        queue.finish("a".to_string(), job.lease).await.unwrap();
+
+        assert_eq!(
+            state(&pool, "a").await,
+            ("new".to_string(), "edit".to_string(), 0, false, false)
+        );
+
+        let edit = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        assert_eq!(edit.post.content, "<p>Edited</p>");
+    }
+
+    #[sqlx::test]
+    async fn retries_edits_of_failed_jobs_that_were_published(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        queue.push(post("b")).await.unwrap();
+        let a = poll_job(&pool, "worker").await.unwrap().unwrap();
+        let b = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        for uri in ["a", "b"] {
+            let mut edit = post(uri);
+            edit.edited_at = Some(OffsetDateTime::now_utc());
+            queue.push(edit).await.unwrap();
+        }
+
+        // Only the first job got as far as publishing its status
+        publish(&pool, "a").await;
+
+        for job in [a, b] {
+            queue
+                .error(
+                    job.post.mastodon_uri,
+                    job.lease,
+                    "failed".to_string(),
+                    &retry(3),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            state(&pool, "a").await,
+            ("new".to_string(), "edit".to_string(), 1, false, false)
+        );
+        assert_eq!(
+            state(&pool, "b").await,
+            ("new".to_string(), "post".to_string(), 1, false, false)
+        );
+    }
+
+    #[sqlx::test]
+    async fn claims_only_jobs_nobody_took(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+
+        let lease = queue.claim(post("a")).await.unwrap();
+
+        assert!(lease.is_some());
+        assert_eq!(state(&pool, "a").await.0, "running");
+        assert!(queue.claim(post("a")).await.unwrap().is_none());
+
+        queue.push(post("b")).await.unwrap();
+
+        assert!(queue.claim(post("b")).await.unwrap().is_some());
+        assert!(poll_job(&pool, "worker").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn turns_jobs_into_deletions(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+
+        assert!(!queue.schedule_deletion("a".to_string()).await.unwrap());
+
+        queue.push(post("a")).await.unwrap();
+
+        assert!(queue.schedule_deletion("a".to_string()).await.unwrap());
+        assert_eq!(
+            state(&pool, "a").await,
+            ("new".to_string(), "deletion".to_string(), 0, false, false)
+        );
+
+        // Deletions are final
+        assert!(!queue.schedule_deletion("a".to_string()).await.unwrap());
+        queue.push(post("a")).await.unwrap();
+
+        assert_eq!(state(&pool, "a").await.1, "deletion");
+    }
+
+    #[sqlx::test]
+    async fn deletes_running_jobs_once_they_finish(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        assert!(queue.schedule_deletion("a".to_string()).await.unwrap());
+        assert_eq!(
+            state(&pool, "a").await,
+            ("running".to_string(), "post".to_string(), 0, true, false)
+        );
+
+        queue.finish("a".to_string(), job.lease).await.unwrap();
+
+        assert_eq!(
+            state(&pool, "a").await,
+            ("new".to_string(), "deletion".to_string(), 0, false, false)
+        );
+    }
+
+    #[sqlx::test]
+    async fn deletes_statuses_of_failed_jobs(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        queue.push(post("b")).await.unwrap();
+        let a = poll_job(&pool, "worker").await.unwrap().unwrap();
+        poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        queue.schedule_deletion("a".to_string()).await.unwrap();
+        queue.schedule_deletion("b".to_string()).await.unwrap();
+
+        // The first job published its status before failing, while the
+        // worker of the second one is gone before publishing anything
+        publish(&pool, "a").await;
+        queue
+            .error("a".to_string(), a.lease, "failed".to_string(), &retry(1))
+            .await
+            .unwrap();
+
+        sqlx::query("update scheduled_posts set lease_expires_at = now() - interval '1 second' where mastodon_uri = 'b'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        queue.reap_expired_leases(&retry(1)).await.unwrap();
+
+        assert_eq!(
+            state(&pool, "a").await,
+            ("new".to_string(), "deletion".to_string(), 0, false, false)
+        );
+        assert!(!is_backing_off(&pool, "a").await);
+        assert_eq!(
+            state(&pool, "b").await,
+            (
+                "finished".to_string(),
+                "deletion".to_string(),
+                0,
+                false,
+                false
+            )
+        );
+    }
+
+    #[sqlx::test]
+    async fn retries_failed_jobs_until_they_run_out_of_attempts(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        let config = retry(2);
+        queue.push(post("a")).await.unwrap();
+
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+        let dead = queue
+            .error("a".to_string(), job.lease, "failed".to_string(), &config)
+            .await
+            .unwrap();
+
+        assert!(!dead);
+        assert_eq!(state(&pool, "a").await.0, "new");
+        assert_eq!(state(&pool, "a").await.2, 1);
+        assert!(is_backing_off(&pool, "a").await);
+        assert!(poll_job(&pool, "worker").await.unwrap().is_none());
+
+        sqlx::query("update scheduled_posts set run_at = now()")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+        let dead = queue
+            .error("a".to_string(), job.lease, "failed".to_string(), &config)
+            .await
+            .unwrap();
+
+        assert!(dead);
+        assert_eq!(state(&pool, "a").await.0, "dead");
+        assert_eq!(state(&pool, "a").await.2, 2);
+    }
+
+    #[sqlx::test]
+    async fn ignores_workers_that_lost_the_lease(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        let stale = Uuid::new_v4();
+        let dead = queue
+            .error("a".to_string(), stale, "failed".to_string(), &retry(1))
+            .await
+            .unwrap();
+        queue.finish("a".to_string(), stale).await.unwrap();
+
+        assert!(!dead);
+        assert!(!queue.renew_lease("a".to_string(), stale).await.unwrap());
+        assert_eq!(state(&pool, "a").await.0, "running");
+    }
+
+    #[sqlx::test]
+    async fn reclaims_jobs_whose_lease_expired(pool: PgPool) {
+        let queue = JobQueue::new(pool.clone());
+        queue.push(post("a")).await.unwrap();
+        queue.push(post("b")).await.unwrap();
+        let job = poll_job(&pool, "worker").await.unwrap().unwrap();
+        poll_job(&pool, "worker").await.unwrap().unwrap();
+
+        // Only the worker of the first job is gone
+        sqlx::query("update scheduled_posts set lease_expires_at = now() - interval '1 second' where mastodon_uri = $1")
+            .bind(&job.post.mastodon_uri)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let reclaimed = queue.reap_expired_leases(&retry(2)).await.unwrap();
+
+        assert_eq!(
+            reclaimed,
+            vec![ReclaimedJob {
+                mastodon_uri: "a".to_string(),
+                worker_id: Some("worker".to_string()),
+                dead: false,
+            }]
+        );
+        assert_eq!(state(&pool, "a").await.0, "new");
+        assert_eq!(state(&pool, "a").await.2, 1);
+        assert!(is_backing_off(&pool, "a").await);
+        assert_eq!(state(&pool, "b").await.0, "running");
+        assert!(!queue.renew_lease("a".to_string(), job.lease).await.unwrap());
+
+        sqlx::query("update scheduled_posts set lease_expires_at = now() - interval '1 second'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let reclaimed = queue.reap_expired_leases(&retry(1)).await.unwrap();
+
+        assert_eq!(reclaimed.len(), 1);
+        assert!(reclaimed[0].dead);
+        assert_eq!(state(&pool, "b").await.0, "dead");
     }
 }
@@ -1,15 +1,19 @@
 use clap::Parser;
 use eyre::{eyre, Result};
-use mastodon_async::prelude::Status;
 use nostr_sdk::prelude::{FromSkStr, Keys, ToBech32};
-use sqlx::{postgres::PgPoolOptions, Pool};
+use sqlx::{postgres::PgPoolOptions, types::Json, Pool};
+use time::OffsetDateTime;
+use url::Url;
 use uuid::Uuid;
 
 pub mod job_queue;
 
-use crate::{health::Timeable, util::extract_instance_url};
+use crate::{health::Timeable, mastodon::Status, util::nip05_name};
 
-use self::job_queue::{JobQueue, ScheduledPost};
+use self::job_queue::{Emoji, JobQueue, ProfileField, ScheduledPost};
+
+/// How far up a thread we look for its root, guarding against reply cycles
+const MAX_THREAD_DEPTH: i32 = 256;
 
 #[derive(Debug, Clone, Parser)]
 pub struct PostgresConfig {
@@ -49,38 +53,57 @@ pub struct MastodonInstance {
 pub struct Profile {
     pub instance_id: Uuid,
     pub user_id: Uuid,
+    /// Full `username@domain` handle
+    pub acct: String,
     pub name: String,
     pub display_name: String,
     pub about: String,
     pub picture: String,
     pub nip05: String,
     pub banner: String,
+    /// Custom emoji used in the display name and bio
+    pub emojis: Vec<Emoji>,
+    /// Url of the profile page on Mastodon
+    pub website: String,
+    /// Profile fields the server verified
+    pub fields: Vec<ProfileField>,
+    pub bot: bool,
 }
 
 impl Profile {
-    pub fn build(instance_id: Uuid, user_id: Uuid, status: &Status) -> Result<Self> {
-        let url = status
-            .url
-            .as_ref()
-            .ok_or_else(|| eyre!("failed to extract instance url"))?;
-        let instance_url = extract_instance_url(url)?;
-
-        let nip05 = format!(
-            "{}.{}",
-            status.account.username.clone(),
-            instance_url.host().unwrap()
-        );
-
-        Ok(Self {
+    pub fn build(instance_id: Uuid, user_id: Uuid, acct: &str, status: &Status) -> Self {
+        Self {
             instance_id,
+            acct: acct.to_string(),
             name: status.account.username.clone(),
             display_name: status.account.display_name.clone(),
             about: status.account.note.clone(),
             user_id,
-            nip05,
+            nip05: nip05_name(acct),
             picture: status.account.avatar.clone(),
             banner: status.account.header.clone(),
-        })
+            emojis: status
+                .account
+                .emojis
+                .iter()
+                .map(|emoji| Emoji {
+                    shortcode: emoji.shortcode.clone(),
+                    url: emoji.url.clone(),
+                })
+                .collect(),
+            website: status.account.url.clone(),
+            fields: status
+                .account
+                .fields
+                .iter()
+                .filter(|field| field.verified_at.is_some())
+                .map(|field| ProfileField {
+                    name: field.name.clone(),
+                    value: field.value.clone(),
+                })
+                .collect(),
+            bot: status.account.bot.unwrap_or(false),
+        }
     }
 }
 
@@ -89,17 +112,22 @@ impl From<ScheduledPost> for Profile {
         Self {
             instance_id: value.instance_id,
             user_id: value.user_id,
+            acct: value.profile_acct,
             name: value.profile_name,
             display_name: value.profile_display_name,
             about: value.profile_about,
             picture: value.profile_picture,
             nip05: value.profile_nip05,
             banner: value.profile_banner,
+            emojis: value.profile_emojis.0,
+            website: value.profile_website,
+            fields: value.profile_fields.0,
+            bot: value.profile_bot,
         }
     }
 }
 
-#[derive(sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "mastodon_post_status")]
 #[sqlx(rename_all = "lowercase")]
 pub enum MastodonPostStatus {
@@ -107,13 +135,34 @@ pub enum MastodonPostStatus {
     Deleted,
 }
 
+/// How the listener should receive updates from a configured server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "mastodon_server_stream")]
+#[sqlx(rename_all = "lowercase")]
+pub enum MastodonStream {
+    /// Poll the public timeline at a fixed interval. The timeline only lists
+    /// statuses as they were first posted, so edits and deletions are not
+    /// mirrored from servers read this way.
+    Polling,
+    /// Stream the federated timeline (`public`)
+    Public,
+    /// Stream only statuses originating from the server (`public:local`)
+    Local,
+}
+
 #[derive(Debug, Clone)]
 pub struct MastodonServer {
+    pub id: Uuid,
     pub instance_url: String,
     pub client_key: String,
     pub client_secret: String,
     pub redirect_url: String,
     pub token: String,
+    pub stream: MastodonStream,
+    /// Domain in the handles of the server's own accounts, as reported by
+    /// the server. It differs from the domain of its url when the server is
+    /// served from a subdomain.
+    pub domain: Option<String>,
 }
 
 impl MastodonServer {
@@ -128,14 +177,43 @@ impl MastodonServer {
             token: this.token.into(),
         }
     }
+
+    /// Returns the domain of the server's own accounts, falling back to the
+    /// domain of its url until the server reported it.
+    pub fn local_domain(&self) -> Result<String> {
+        if let Some(domain) = &self.domain {
+            return Ok(domain.clone());
+        }
+
+        let url = Url::parse(&self.instance_url)?;
+
+        Ok(url
+            .host_str()
+            .ok_or_else(|| eyre!("server url has no host"))?
+            .to_string())
+    }
 }
 
+#[derive(Debug, Clone)]
 pub struct MastodonPost {
     pub instance_id: Uuid,
     pub user_id: Uuid,
     pub mastodon_id: String,
+    pub mastodon_uri: String,
+    pub in_reply_to: Option<String>,
     pub nostr_id: String,
     pub status: MastodonPostStatus,
+    /// NIP-88 poll published along with the note, if any
+    pub poll_nostr_id: Option<String>,
+}
+
+/// A mirrored status in the thread above a reply.
+pub struct ThreadPost {
+    pub nostr_id: String,
+    pub nostr_public_key: String,
+    /// Whether the status starts the thread, rather than replying to one
+    /// that was not mirrored or lies beyond the depth we walk
+    pub is_root: bool,
 }
 
 pub enum ChangeResult {
@@ -165,6 +243,11 @@ impl Postgres {
         Ok(Self { pool })
     }
 
+    #[cfg(test)]
+    pub fn with_pool(pool: Pool<sqlx::Postgres>) -> Self {
+        Self { pool }
+    }
+
     pub async fn health_check(&self) -> Result<()> {
         sqlx::query("select 1")
             .execute(&self.pool)
@@ -183,30 +266,135 @@ impl Postgres {
     }
 
     pub async fn fetch_servers(&self) -> Result<Vec<MastodonServer>> {
-        Ok(sqlx::query_as!(MastodonServer, "select instance_url, client_key, client_secret, redirect_url, token from mastodon_servers")
+        Ok(sqlx::query_as!(MastodonServer, r#"select id, instance_url, client_key, client_secret, redirect_url, token, stream as "stream: MastodonStream", domain from mastodon_servers"#)
             .fetch_all(&self.pool).time_as("postgres.fetch_servers").await?)
     }
 
+    pub async fn fetch_server(&self, server_id: Uuid) -> Result<MastodonServer> {
+        Ok(sqlx::query_as!(MastodonServer, r#"select id, instance_url, client_key, client_secret, redirect_url, token, stream as "stream: MastodonStream", domain from mastodon_servers where id = $1"#, server_id)
+            .fetch_one(&self.pool).time_as("postgres.fetch_server").await?)
+    }
+
+    pub async fn update_server_domain(&self, server_id: Uuid, domain: &str) -> Result<()> {
+        sqlx::query!(
+            "update mastodon_servers set domain = $2 where id = $1",
+            server_id,
+            domain
+        )
+        .execute(&self.pool)
+        .time_as("postgres.update_server_domain")
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the id of the newest status already read from the server's
+    /// timeline, if any.
+    pub async fn fetch_server_cursor(&self, server_id: Uuid) -> Result<Option<String>> {
+        Ok(sqlx::query!(
+            "select last_status_id from mastodon_servers where id = $1",
+            server_id
+        )
+        .fetch_one(&self.pool)
+        .time_as("postgres.fetch_server_cursor")
+        .await?
+        .last_status_id)
+    }
+
+    /// Moves the server's timeline cursor to `status_id`, unless it already
+    /// points to a newer status.
+    pub async fn advance_server_cursor(&self, server_id: Uuid, status_id: String) -> Result<()> {
+        sqlx::query!(
+            "update mastodon_servers set last_status_id = $2
+            where id = $1 and (
+                last_status_id is null
+                or (length($2), $2) > (length(last_status_id), last_status_id)
+            )",
+            server_id,
+            status_id
+        )
+        .execute(&self.pool)
+        .time_as("postgres.advance_server_cursor")
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn fetch_nostr_relays(&self) -> Result<Vec<String>> {
-        Ok(sqlx::query!("select url from nostr_relays")
-            .fetch_all(&self.pool)
-            .time_as("postgres.fetch_relays")
-            .await?
-            .into_iter()
-            .map(|x| x.url)
-            .collect())
+        Ok(
+            sqlx::query!("select url from nostr_relays order by created_at, url")
+                .fetch_all(&self.pool)
+                .time_as("postgres.fetch_relays")
+                .await?
+                .into_iter()
+                .map(|x| x.url)
+                .collect(),
+        )
+    }
+
+    /// Checks whether a profile, or the metadata it is published as, differs
+    /// from the one last published for its user.
+    pub async fn is_profile_changed(&self, profile: &Profile, metadata: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"select not exists (
+                select 1 from profiles
+                where user_id = $1
+                    and (name, display_name, about, picture, nip05, banner, website, fields, bot,
+                         emojis, metadata)
+                    is not distinct from
+                        ($2::text, $3::text, $4::text, $5::text, $6::text, $7::text, $8::text,
+                         $9::jsonb, $10::boolean, $11::jsonb, $12::text)
+            ) as "changed!""#,
+            profile.user_id,
+            profile.name,
+            profile.display_name,
+            profile.about,
+            profile.picture,
+            profile.nip05,
+            profile.banner,
+            profile.website,
+            Json(profile.fields.clone()) as Json<Vec<ProfileField>>,
+            profile.bot,
+            Json(profile.emojis.clone()) as Json<Vec<Emoji>>,
+            metadata,
+        )
+        .fetch_one(&self.pool)
+        .time_as("postgres.is_profile_changed")
+        .await?;
+
+        Ok(result.changed)
     }
 
-    pub async fn update_profile(&self, profile: &Profile) -> Result<ChangeResult> {
-        sqlx::query_as!(
+    /// Records the profile published for a user, and the metadata it was
+    /// published as. Returns whether it differs from the one recorded before.
+    pub async fn update_profile(&self, profile: &Profile, metadata: &str) -> Result<ChangeResult> {
+        let result = sqlx::query_as!(
             ResultContainer,
             "insert into profiles
-                (instance_id, user_id, name, display_name, about, picture, nip05, banner)
+                (instance_id, user_id, name, display_name, about, picture, nip05, banner,
+                 website, fields, bot, emojis, metadata)
             values
-                ($1, $2, $3, $4, $5, $6, $7, $8)
+                ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             on conflict (user_id) do update set
-                name = $3, display_name = $4, about = $5, picture = $6, nip05 = $7, banner = $8
-            returning case when xmax = 0 then id::text else 'unchanged' end as result",
+                name = excluded.name,
+                display_name = excluded.display_name,
+                about = excluded.about,
+                picture = excluded.picture,
+                nip05 = excluded.nip05,
+                banner = excluded.banner,
+                website = excluded.website,
+                fields = excluded.fields,
+                bot = excluded.bot,
+                emojis = excluded.emojis,
+                metadata = excluded.metadata
+            where (profiles.name, profiles.display_name, profiles.about, profiles.picture,
+                   profiles.nip05, profiles.banner, profiles.website, profiles.fields,
+                   profiles.bot, profiles.emojis, profiles.metadata)
+                is distinct from
+                  (excluded.name, excluded.display_name, excluded.about, excluded.picture,
+                   excluded.nip05, excluded.banner, excluded.website, excluded.fields,
+                   excluded.bot, excluded.emojis, excluded.metadata)
+            returning id::text as result",
             profile.instance_id,
             profile.user_id,
             profile.name,
@@ -214,36 +402,84 @@ impl Postgres {
             profile.about,
             profile.picture,
             profile.nip05,
-            profile.banner
+            profile.banner,
+            profile.website,
+            Json(profile.fields.clone()) as Json<Vec<ProfileField>>,
+            profile.bot,
+            Json(profile.emojis.clone()) as Json<Vec<Emoji>>,
+            metadata,
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .time_as("postgres.update_profile")
-        .await?
-        .to_change_result()
+        .await?;
+
+        match result {
+            Some(result) => result.to_change_result(),
+            None => Ok(ChangeResult::Unchanged),
+        }
+    }
+
+    /// Walks up a thread starting from the status being replied to, returning
+    /// every mirrored ancestor from the parent up to the oldest one we know.
+    /// The thread is cut short when an ancestor was never mirrored.
+    pub async fn fetch_thread(&self, parent_uri: String) -> Result<Vec<ThreadPost>> {
+        Ok(sqlx::query_as!(
+            ThreadPost,
+            r#"with recursive thread (mastodon_uri, in_reply_to, nostr_id, user_id, depth) as (
+                select mastodon_uri, in_reply_to, nostr_id, user_id, 0
+                from mastodon_posts where mastodon_uri = $1
+                union all
+                select p.mastodon_uri, p.in_reply_to, p.nostr_id, p.user_id, t.depth + 1
+                from mastodon_posts p join thread t on p.mastodon_uri = t.in_reply_to
+                where t.depth < $2
+            )
+            select t.nostr_id as "nostr_id!", u.nostr_public_key as "nostr_public_key!",
+                t.in_reply_to is null as "is_root!"
+            from thread t join users u on u.id = t.user_id
+            order by t.depth"#,
+            parent_uri,
+            MAX_THREAD_DEPTH
+        )
+        .fetch_all(&self.pool)
+        .time_as("postgres.fetch_thread")
+        .await?)
     }
 
-    pub async fn fetch_nostr_id(&self, mastodon_id: String) -> Result<Option<String>> {
+    /// Finds the canonical URI of a status we have already seen, given its id
+    /// on the server that delivered it. Statuses from before we kept track of
+    /// the server are matched by their id alone.
+    pub async fn fetch_status_uri(
+        &self,
+        server_id: Uuid,
+        mastodon_id: String,
+    ) -> Result<Option<String>> {
         let result = sqlx::query!(
-            r#"select nostr_id from mastodon_posts where mastodon_id = $1"#,
+            r#"select mastodon_uri from scheduled_posts
+            where (server_id = $1 or server_id is null) and mastodon_id = $2
+            order by server_id is null
+            limit 1"#,
+            server_id,
             mastodon_id
         )
         .fetch_optional(&self.pool)
-        .time_as("postgres.fetch_nostr_id")
+        .time_as("postgres.fetch_status_uri")
         .await?;
 
-        Ok(result.map(|x| x.nostr_id))
+        Ok(result.map(|x| x.mastodon_uri))
     }
 
     pub async fn add_post(&self, post: MastodonPost) -> Result<ChangeResult> {
         let result = sqlx::query!(
             r#"insert into mastodon_posts
-                (instance_id, user_id, mastodon_id, nostr_id, status)
-            values ($1, $2, $3, $4, $5)
-            on conflict (mastodon_id) do nothing
+                (instance_id, user_id, mastodon_id, mastodon_uri, in_reply_to, nostr_id, status)
+            values ($1, $2, $3, $4, $5, $6, $7)
+            on conflict (mastodon_uri) do nothing
             returning id as result"#,
             post.instance_id,
             post.user_id,
             post.mastodon_id,
+            post.mastodon_uri,
+            post.in_reply_to,
             post.nostr_id,
             post.status as MastodonPostStatus
         )
@@ -257,6 +493,100 @@ impl Postgres {
         }
     }
 
+    pub async fn fetch_post(&self, mastodon_uri: String) -> Result<Option<MastodonPost>> {
+        Ok(sqlx::query_as!(
+            MastodonPost,
+            r#"select instance_id, user_id, mastodon_id, mastodon_uri, in_reply_to, nostr_id, status as "status: MastodonPostStatus", poll_nostr_id
+            from mastodon_posts where mastodon_uri = $1"#,
+            mastodon_uri
+        )
+        .fetch_optional(&self.pool)
+        .time_as("postgres.fetch_post")
+        .await?)
+    }
+
+    /// Points a mirrored status to the event of its newest version.
+    pub async fn update_post(&self, mastodon_uri: String, nostr_id: String) -> Result<()> {
+        sqlx::query!(
+            "update mastodon_posts set nostr_id = $1 where mastodon_uri = $2",
+            nostr_id,
+            mastodon_uri
+        )
+        .execute(&self.pool)
+        .time_as("postgres.update_post")
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records the poll event published for a mirrored status.
+    pub async fn update_post_poll(
+        &self,
+        mastodon_uri: String,
+        poll_nostr_id: Option<String>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "update mastodon_posts set poll_nostr_id = $1 where mastodon_uri = $2",
+            poll_nostr_id,
+            mastodon_uri
+        )
+        .execute(&self.pool)
+        .time_as("postgres.update_post_poll")
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_post_version(
+        &self,
+        mastodon_uri: String,
+        edited_at: Option<OffsetDateTime>,
+        nostr_id: String,
+    ) -> Result<()> {
+        sqlx::query!(
+            "insert into mastodon_post_versions (mastodon_uri, edited_at, nostr_id)
+            values ($1, $2, $3)
+            on conflict (nostr_id) do nothing",
+            mastodon_uri,
+            edited_at,
+            nostr_id
+        )
+        .execute(&self.pool)
+        .time_as("postgres.add_post_version")
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the events of every version of a mirrored status, oldest first.
+    pub async fn fetch_post_versions(&self, mastodon_uri: String) -> Result<Vec<String>> {
+        Ok(sqlx::query!(
+            "select nostr_id from mastodon_post_versions
+            where mastodon_uri = $1
+            order by edited_at nulls first",
+            mastodon_uri
+        )
+        .fetch_all(&self.pool)
+        .time_as("postgres.fetch_post_versions")
+        .await?
+        .into_iter()
+        .map(|x| x.nostr_id)
+        .collect())
+    }
+
+    pub async fn mark_post_deleted(&self, mastodon_uri: String) -> Result<()> {
+        sqlx::query!(
+            "update mastodon_posts set status = $1 where mastodon_uri = $2",
+            MastodonPostStatus::Deleted as MastodonPostStatus,
+            mastodon_uri
+        )
+        .execute(&self.pool)
+        .time_as("postgres.mark_post_deleted")
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn fetch_credentials(&self, user_id: Uuid) -> Result<Keys> {
         let result = sqlx::query!(
             "select nostr_public_key, nostr_private_key from users where id = $1 limit 1",
@@ -294,10 +624,12 @@ impl Postgres {
         })
     }
 
+    /// Fetches the user for a full `username@domain` handle, creating it with a
+    /// new keypair if we have never seen it before.
     pub async fn fetch_or_create_user<T: Into<String> + Send>(
         &self,
         instance_id: Uuid,
-        username: T,
+        acct: T,
     ) -> Result<User> {
         let new_keypair = Keys::generate();
 
@@ -305,12 +637,12 @@ impl Postgres {
             "insert into users
                 (instance_id, nostr_public_key, nostr_private_key, mastodon_user)
             values ($1, $2, $3, $4)
-            on conflict (mastodon_user) do update set instance_id = $1
+            on conflict (mastodon_user) where merged_into is null do update set instance_id = $1
             returning id, nostr_private_key",
             instance_id,
             new_keypair.public_key().to_bech32()?,
             new_keypair.secret_key().unwrap().to_bech32()?,
-            username.into()
+            acct.into()
         )
         .fetch_one(&self.pool)
         .time_as("postgres.fetch_or_create_user")
@@ -322,6 +654,57 @@ impl Postgres {
         })
     }
 
+    /// Returns the public key of the mirrored user for a full `username@domain`
+    /// handle, if we have one.
+    pub async fn fetch_public_key(&self, acct: String) -> Result<Option<String>> {
+        let result = sqlx::query!(
+            "select nostr_public_key from users where mastodon_user = $1 and merged_into is null",
+            acct
+        )
+        .fetch_optional(&self.pool)
+        .time_as("postgres.fetch_public_key")
+        .await?;
+
+        Ok(result.map(|x| x.nostr_public_key))
+    }
+
+    /// Finds the public key of the mirrored user behind the local part of a
+    /// NIP-05 identifier.
+    pub async fn fetch_nip05_public_key(&self, name: String) -> Result<Option<String>> {
+        let result = sqlx::query!(
+            "select u.nostr_public_key from profiles p
+            join users u on u.id = p.user_id
+            where p.nip05 = $1 and u.merged_into is null",
+            name
+        )
+        .fetch_optional(&self.pool)
+        .time_as("postgres.fetch_nip05_public_key")
+        .await?;
+
+        Ok(result.map(|x| x.nostr_public_key))
+    }
+
+    /// Checks a language against the `language_rules` table. Denied languages
+    /// are never mirrored, and once any language is allowed, only allowed
+    /// languages are. Statuses without a language only pass the latter rule
+    /// when there are no allowed languages.
+    pub async fn is_language_allowed(&self, language: Option<String>) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"select
+                not exists (select 1 from language_rules where rule = 'deny' and lower(language) = $1)
+                and (
+                    not exists (select 1 from language_rules where rule = 'allow')
+                    or exists (select 1 from language_rules where rule = 'allow' and lower(language) = $1)
+                ) as "allowed!""#,
+            language
+        )
+        .fetch_one(&self.pool)
+        .time_as("postgres.is_language_allowed")
+        .await?;
+
+        Ok(result.allowed)
+    }
+
     pub async fn is_user_blacklisted(&self, user_id: Uuid) -> Result<bool> {
         let result = sqlx::query!("select id from user_blacklists where user_id = $1", user_id)
             .fetch_optional(&self.pool)
@@ -333,3 +716,36 @@ impl Postgres {
         JobQueue::new(self.pool.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(instance_url: &str, domain: Option<&str>) -> MastodonServer {
+        MastodonServer {
+            id: Uuid::nil(),
+            instance_url: instance_url.to_string(),
+            client_key: String::new(),
+            client_secret: String::new(),
+            redirect_url: String::new(),
+            token: String::new(),
+            stream: MastodonStream::Public,
+            domain: domain.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn local_domain_is_the_reported_one() {
+        let server = server("https://social.example.com/", Some("example.com"));
+
+        assert_eq!(server.local_domain().unwrap(), "example.com");
+    }
+
+    #[test]
+    fn local_domain_falls_back_to_the_server_host() {
+        let server = server("https://social.example.com/", None);
+
+        assert_eq!(server.local_domain().unwrap(), "social.example.com");
+        assert!(self::server("not a url", None).local_domain().is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Renders the HTML of Mastodon statuses as the plain text most Nostr clients
+//! expect.
+
+use std::collections::HashMap;
+
+use eyre::Result;
+use html5ever::{driver::ParseOpts, parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+
+/// Renders the HTML content of a status. Links to the profiles in `mentions`
+/// are replaced by the text they map to.
+pub fn render(html: &str, mentions: &HashMap<String, String>) -> Result<String> {
+    let dom = parse_document(RcDom::default(), ParseOpts::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())?;
+
+    let renderer = Renderer { mentions };
+    let mut output = Output::default();
+
+    renderer.walk(&dom.document, &mut output);
+
+    Ok(output.finish())
+}
+
+/// Accumulates rendered text, collapsing whitespace the way a browser would
+/// and only emitting line breaks once there is text after them.
+#[derive(Default)]
+struct Output {
+    text: String,
+    newlines: usize,
+    space: bool,
+}
+
+impl Output {
+    fn flush(&mut self) {
+        if self.text.is_empty() {
+            self.newlines = 0;
+            self.space = false;
+            return;
+        }
+
+        if self.newlines > 0 {
+            self.text.truncate(self.text.trim_end_matches(' ').len());
+            self.text.push_str(&"\n".repeat(self.newlines));
+        } else if self.space && !self.text.ends_with(&['\n', ' '][..]) {
+            self.text.push(' ');
+        }
+
+        self.newlines = 0;
+        self.space = false;
+    }
+
+    fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+            } else {
+                self.flush();
+                self.text.push(c);
+            }
+        }
+    }
+
+    /// Pushes text as is, without collapsing its whitespace.
+    fn push_raw(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.flush();
+        self.text.push_str(text);
+    }
+
+    fn line_break(&mut self) {
+        self.newlines += 1;
+        self.space = false;
+    }
+
+    /// Separates blocks, like paragraphs, with an empty line.
+    fn block(&mut self) {
+        self.newlines = self.newlines.max(2);
+        self.space = false;
+    }
+
+    fn finish(self) -> String {
+        self.text.trim_end().to_string()
+    }
+}
+
+struct Renderer<'a> {
+    mentions: &'a HashMap<String, String>,
+}
+
+impl Renderer<'_> {
+    fn walk(&self, node: &Handle, output: &mut Output) {
+        let (name, attrs) = match &node.data {
+            NodeData::Text { contents } => return output.push_text(&contents.borrow()),
+            NodeData::Element { name, attrs, .. } => (name.local.to_string(), attrs),
+            NodeData::Document => return self.walk_children(node, output),
+            _ => return,
+        };
+
+        let attr = |key: &str| {
+            attrs
+                .borrow()
+                .iter()
+                .find(|attr| attr.name.local.as_ref() == key)
+                .map(|attr| attr.value.to_string())
+        };
+
+        match name.as_str() {
+            "head" | "script" | "style" => {}
+            "br" => output.line_break(),
+            "p" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                output.block();
+                self.walk_children(node, output);
+                output.block();
+            }
+            "pre" => {
+                output.block();
+                output.push_raw(text_content(node, true).trim_end());
+                output.block();
+            }
+            "blockquote" => {
+                let quote = self.render_children(node);
+
+                output.block();
+                output.push_raw(&prefix_lines(&quote, "> ", "> "));
+                output.block();
+            }
+            "ul" | "ol" => {
+                output.block();
+
+                let items = node.children.borrow().iter().filter(|child| {
+                    matches!(&child.data, NodeData::Element { name, .. } if name.local.as_ref() == "li")
+                }).cloned().collect::<Vec<_>>();
+
+                for (i, item) in items.iter().enumerate() {
+                    let marker = match name.as_str() {
+                        "ol" => format!("{}. ", i + 1),
+                        _ => "- ".to_string(),
+                    };
+                    let indent = " ".repeat(marker.len());
+
+                    output.push_raw(&prefix_lines(&self.render_children(item), &marker, &indent));
+                    output.line_break();
+                }
+
+                output.block();
+            }
+            // Some servers embed custom emoji as images, with the shortcode as
+            // their alternative text.
+            "img" => {
+                if let Some(alt) = attr("alt") {
+                    output.push_text(&alt);
+                }
+            }
+            "a" => match attr("href") {
+                Some(href) => self.link(node, &href, attr("class"), output),
+                None => self.walk_children(node, output),
+            },
+            _ => self.walk_children(node, output),
+        }
+    }
+
+    fn walk_children(&self, node: &Handle, output: &mut Output) {
+        for child in node.children.borrow().iter() {
+            self.walk(child, output);
+        }
+    }
+
+    fn render_children(&self, node: &Handle) -> String {
+        let mut output = Output::default();
+
+        self.walk_children(node, &mut output);
+
+        output.finish()
+    }
+
+    fn link(&self, node: &Handle, href: &str, class: Option<String>, output: &mut Output) {
+        if let Some(mention) = self.mentions.get(href) {
+            return output.push_raw(mention);
+        }
+
+        let classes = class.unwrap_or_default();
+        let visible = text_content(node, false);
+        let visible = visible.trim();
+
+        if classes.split_whitespace().any(|class| class == "hashtag") || visible.starts_with('#') {
+            return output.push_raw(visible);
+        }
+
+        // Mastodon hides parts of long urls, but keeps them in the markup
+        let full = text_content(node, true);
+        let full = strip_scheme(full.trim()).trim_end_matches('/');
+
+        if full.is_empty() || strip_scheme(href).starts_with(full) {
+            output.push_raw(href);
+        } else {
+            output.push_raw(&format!("{visible} ({href})"));
+        }
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    url.trim_start_matches("https://")
+        .trim_start_matches("http://")
+}
+
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            format!("{prefix}{line}").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Concatenates the text of a node, optionally including the parts of links
+/// Mastodon marks as `invisible`.
+fn text_content(node: &Handle, include_invisible: bool) -> String {
+    let mut text = String::new();
+
+    match &node.data {
+        NodeData::Text { contents } => text.push_str(&contents.borrow()),
+        NodeData::Element { name, attrs, .. } => {
+            let invisible = attrs.borrow().iter().any(|attr| {
+                attr.name.local.as_ref() == "class"
+                    && attr
+                        .value
+                        .split_whitespace()
+                        .any(|class| class == "invisible")
+            });
+
+            if invisible && !include_invisible {
+                return text;
+            }
+
+            if name.local.as_ref() == "br" {
+                text.push('\n');
+            }
+
+            for child in node.children.borrow().iter() {
+                text.push_str(&text_content(child, include_invisible));
+            }
+        }
+        _ => {}
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, path::Path};
+
+    use super::render;
+
+    /// Renders every `.html` file in `tests/fixtures/render`, comparing it with
+    /// the `.txt` file of the same name. Set `UPDATE_GOLDEN_FILES` to rewrite
+    /// them instead.
+    #[test]
+    fn golden_files() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/render");
+        let update = std::env::var_os("UPDATE_GOLDEN_FILES").is_some();
+
+        let mentions = HashMap::from([(
+            "https://mastodon.social/@Gargron".to_string(),
+            "nostr:npub1sg6plzptd64u62a878hep2kev88swjh3tw00gjsfl8f237lmu63q0uf63m".to_string(),
+        )]);
+
+        let mut checked = 0;
+
+        for entry in fs::read_dir(&fixtures).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some("html") {
+                continue;
+            }
+
+            let html = fs::read_to_string(&path).unwrap();
+            let rendered = render(&html, &mentions).unwrap();
+            let golden = path.with_extension("txt");
+
+            if update {
+                fs::write(&golden, format!("{rendered}\n")).unwrap();
+            } else {
+                let expected = fs::read_to_string(&golden).unwrap();
+                assert_eq!(rendered, expected.trim_end(), "{}", path.display());
+            }
+
+            checked += 1;
+        }
+
+        assert!(checked > 0, "no golden files in {}", fixtures.display());
+    }
+}
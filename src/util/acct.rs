@@ -0,0 +1,86 @@
+/// Returns the full `username@domain` handle of an account. Accounts that are
+/// local to the server that delivered them only carry their username, so the
+/// domain of the server's accounts is used in that case.
+pub fn full_acct(acct: &str, local_domain: &str) -> String {
+    if acct.contains('@') {
+        return acct.to_lowercase();
+    }
+
+    format!("{acct}@{local_domain}").to_lowercase()
+}
+
+/// Turns a handle into the local part of its NIP-05 identifier, which may only
+/// hold lowercase letters, digits and `-_.`. The `@` becomes `_at_`, so that
+/// `alice@mastodon.social` is `alice_at_mastodon.social`. Underscores are
+/// doubled and any other character is written as its code point between `_x`
+/// and `_`, so that no two handles share a name.
+pub fn nip05_name(acct: &str) -> String {
+    let mut name = String::new();
+
+    for c in acct.to_lowercase().chars() {
+        match c {
+            'a'..='z' | '0'..='9' | '-' | '.' => name.push(c),
+            '_' => name.push_str("__"),
+            '@' => name.push_str("_at_"),
+            _ => name.push_str(&format!("_x{:x}_", c as u32)),
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_acct_keeps_remote_handles() {
+        assert_eq!(
+            full_acct("Alice@Example.Social", "mastodon.social"),
+            "alice@example.social"
+        );
+    }
+
+    #[test]
+    fn full_acct_adds_the_server_domain_to_local_handles() {
+        assert_eq!(
+            full_acct("Alice", "mastodon.social"),
+            "alice@mastodon.social"
+        );
+    }
+
+    #[test]
+    fn nip05_name_separates_username_and_domain() {
+        assert_eq!(
+            nip05_name("Alice@mastodon.social"),
+            "alice_at_mastodon.social"
+        );
+    }
+
+    #[test]
+    fn nip05_name_escapes_other_characters() {
+        assert_eq!(nip05_name("a_b@c.social"), "a__b_at_c.social");
+        assert_eq!(nip05_name("a+b@c.social"), "a_x2b_b_at_c.social");
+        assert_eq!(nip05_name("jos\u{e9}@c.social"), "jos_xe9__at_c.social");
+    }
+
+    #[test]
+    fn nip05_name_does_not_collide() {
+        let accts = [
+            "a.b@c.social",
+            "a@b.c.social",
+            "a_b@c.social",
+            "a-b@c.social",
+            "a+b@c.social",
+            "a_at_b@c.social",
+            "a@at.b.c.social",
+            "a_x2b_b@c.social",
+        ];
+
+        let mut names: Vec<String> = accts.iter().map(|acct| nip05_name(acct)).collect();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), accts.len());
+    }
+}
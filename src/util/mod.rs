@@ -1,3 +1,11 @@
+mod acct;
+mod sse;
+mod status_id;
+mod template;
 mod url;
 
+pub use self::acct::*;
+pub use self::sse::*;
+pub use self::status_id::*;
+pub use self::template::*;
 pub use self::url::*;
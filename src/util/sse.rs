@@ -0,0 +1,93 @@
+//! Reader for server-sent events, which the Mastodon streaming API is served
+//! as. mastodon-async can stream timelines as well, but it does not deliver
+//! `status.update` events, which is how edits are announced.
+
+use std::mem;
+
+/// A server-sent event, with the lines of its data joined.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// Splits a stream of bytes into server-sent events. Chunks can end anywhere,
+/// so incomplete lines are kept until the rest of them arrives.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    pending: SseEvent,
+}
+
+impl SseParser {
+    /// Reads a chunk of the stream, returning the events it completed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = vec![];
+
+        self.buffer.extend_from_slice(chunk);
+
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+
+            if line.is_empty() {
+                // Comments used as heartbeats end up as empty events
+                if self.pending != SseEvent::default() {
+                    events.push(mem::take(&mut self.pending));
+                }
+            } else if let Some(value) = line.strip_prefix("event:") {
+                self.pending.event = value.trim().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !self.pending.data.is_empty() {
+                    self.pending.data.push('\n');
+                }
+
+                self.pending.data.push_str(value.trim_start());
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: &str, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        let mut parser = SseParser::default();
+
+        assert_eq!(parser.feed(b"event: upd"), vec![]);
+        assert_eq!(parser.feed(b"ate\r\ndata: {\"id\":"), vec![]);
+        assert_eq!(
+            parser.feed(b"\"1\"}\r\n\r\n"),
+            vec![event("update", "{\"id\":\"1\"}")]
+        );
+    }
+
+    #[test]
+    fn joins_data_lines() {
+        let mut parser = SseParser::default();
+
+        assert_eq!(
+            parser.feed(b"event: delete\ndata: 1\ndata: 2\n\nevent: delete\ndata: 3\n\n"),
+            vec![event("delete", "1\n2"), event("delete", "3")]
+        );
+    }
+
+    #[test]
+    fn skips_heartbeats() {
+        let mut parser = SseParser::default();
+
+        assert_eq!(parser.feed(b":thump\n\n"), vec![]);
+    }
+}
@@ -0,0 +1,29 @@
+use std::cmp::Ordering;
+
+/// Orders Mastodon status ids. They are numeric strings that do not fit in a
+/// `u64` on every server, so a longer id is always the newer one.
+pub fn cmp_status_ids(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::cmp_status_ids;
+
+    #[test]
+    fn longer_ids_are_newer() {
+        assert_eq!(cmp_status_ids("99", "100"), Ordering::Less);
+        assert_eq!(
+            cmp_status_ids("109876543210987654321", "18446744073709551615"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn compares_ids_of_the_same_length_as_numbers() {
+        assert_eq!(cmp_status_ids("110", "109"), Ordering::Greater);
+        assert_eq!(cmp_status_ids("109", "109"), Ordering::Equal);
+    }
+}
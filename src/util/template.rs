@@ -0,0 +1,72 @@
+/// Fills the `{placeholder}`s of a configured template in a single pass, so
+/// braces in the values are never expanded. Unknown placeholders are left as
+/// they are.
+pub fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(key, _)| *key == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                output.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fill_template;
+
+    #[test]
+    fn fills_placeholders() {
+        let values = [("name", "alice"), ("instance", "example.com")];
+
+        assert_eq!(
+            fill_template("{name}@{instance}", &values),
+            "alice@example.com"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_placeholders() {
+        assert_eq!(
+            fill_template("{name} {nickname}", &[("name", "alice")]),
+            "alice {nickname}"
+        );
+    }
+
+    #[test]
+    fn leaves_unclosed_braces() {
+        assert_eq!(
+            fill_template("{name} {name", &[("name", "alice")]),
+            "alice {name"
+        );
+        assert_eq!(fill_template("{ {name}", &[("name", "alice")]), "{ alice");
+    }
+
+    #[test]
+    fn does_not_expand_values() {
+        let values = [("name", "{about}"), ("about", "bio")];
+
+        assert_eq!(fill_template("{name}: {about}", &values), "{about}: bio");
+    }
+}
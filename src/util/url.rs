@@ -15,3 +15,47 @@ pub fn base_url(mut url: Url) -> Result<Url> {
 pub fn extract_instance_url<S: AsRef<str>>(input: S) -> Result<Url> {
     base_url(Url::parse(input.as_ref())?)
 }
+
+/// Returns the ActivityPub actor of a status, derived from the URI Mastodon
+/// gives its statuses (`<actor>/statuses/<id>`). Servers that name their
+/// statuses differently do not tell who the actor is.
+pub fn actor_uri(status_uri: &str) -> Option<String> {
+    let mut url = Url::parse(status_uri).ok()?;
+    let mut path = url.path_segments()?;
+
+    let actor = match (path.next(), path.next(), path.next()) {
+        (Some("users"), Some(username), Some("statuses")) if !username.is_empty() => {
+            format!("/users/{username}")
+        }
+        _ => return None,
+    };
+
+    url.set_path(&actor);
+    url.set_query(None);
+    url.set_fragment(None);
+
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actor_uri_of_statuses_and_boosts() {
+        assert_eq!(
+            actor_uri("https://mastodon.social/users/alice/statuses/109"),
+            Some("https://mastodon.social/users/alice".to_string())
+        );
+        assert_eq!(
+            actor_uri("https://mastodon.social/users/alice/statuses/110/activity"),
+            Some("https://mastodon.social/users/alice".to_string())
+        );
+    }
+
+    #[test]
+    fn actor_uri_of_other_servers_is_unknown() {
+        assert_eq!(actor_uri("https://pleroma.site/objects/7f1c"), None);
+        assert_eq!(actor_uri("urn:nostodon:legacy:109"), None);
+    }
+}